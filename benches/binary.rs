@@ -129,6 +129,54 @@ fn benchmarks(c: &mut Criterion) {
         },
     );
 
+    group.bench_with_input(
+        BenchmarkId::new("v2::FixedBuilder::build", "IPv6 with TLVs"),
+        &addresses,
+        |b, &a| {
+            let mut buffer = [0; 128];
+
+            b.iter(|| {
+                v2::FixedBuilder::with_addresses(
+                    buffer.as_mut_slice(),
+                    v2::Version::Two | v2::Command::Local,
+                    v2::Protocol::Unspecified,
+                    a,
+                )
+                .unwrap()
+                .write_tlv(v2::Type::NoOp, [0].as_slice())
+                .unwrap()
+                .write_tlv(v2::Type::NoOp, [42].as_slice())
+                .unwrap()
+                .build()
+                .unwrap()
+                .len()
+            })
+        },
+    );
+
+    let template = v2::HeaderTemplate::try_from(
+        v2::Builder::with_addresses(
+            v2::Version::Two | v2::Command::Local,
+            v2::Protocol::Unspecified,
+            addresses,
+        )
+        .write_tlv(v2::Type::NoOp, [0].as_slice())
+        .unwrap()
+        .write_tlv(v2::Type::NoOp, [42].as_slice())
+        .unwrap(),
+    )
+    .unwrap();
+
+    group.bench_with_input(
+        BenchmarkId::new("v2::HeaderTemplate::render_addresses", "IPv6 with TLVs"),
+        &addresses,
+        |b, a| {
+            let mut buffer = [0; 128];
+
+            b.iter(|| template.render_addresses(a, &mut buffer).unwrap())
+        },
+    );
+
     group.finish();
 }
 
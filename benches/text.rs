@@ -69,6 +69,16 @@ fn benchmarks(c: &mut Criterion) {
                 b.iter(|| a.to_string());
             },
         );
+
+        group.bench_with_input(
+            BenchmarkId::new("v1::Addresses::write_to", id),
+            &header.addresses,
+            |b, a| {
+                let mut buffer = [0; 107];
+
+                b.iter(|| a.write_to(&mut buffer).unwrap());
+            },
+        );
     }
 
     group.finish();
@@ -19,7 +19,7 @@ fn handle_connection(mut client_input: TcpStream, server_address: &SocketAddr) -
         let client_address = client_input.peer_addr()?;
         let server_address = server_output.peer_addr()?;
 
-        match args().nth(1).as_ref().map(String::as_str) {
+        match args().nth(1).as_deref() {
             Some("v1") => {
                 println!("Writing v1 header.");
 
@@ -50,8 +50,8 @@ fn handle_connection(mut client_input: TcpStream, server_address: &SocketAddr) -
 
         let mut buffer = [0; 512];
 
-        client_input.read(&mut buffer)?;
-        server_output.write_all(&mut buffer)?;
+        let read = client_input.read(&mut buffer)?;
+        server_output.write_all(&buffer[..read])?;
         server_output.flush()
     });
     let server_to_client = spawn(move || std::io::copy(&mut server_input, &mut client_output));
@@ -66,7 +66,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let listen_address = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 8888);
     let proxy_address = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 7777);
 
-    let listener = TcpListener::bind(&listen_address).unwrap();
+    let listener = TcpListener::bind(listen_address).unwrap();
 
     println!("Listening on: {}", listen_address);
     println!("Proxying to: {}", proxy_address);
@@ -5,20 +5,19 @@ use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
 const RESPONSE: &str = "HTTP/1.1 200 OK\r\n\r\n";
 
 fn handle_connection(mut client: TcpStream) -> io::Result<()> {
-    let mut buffer = [0; 512];
-    let mut read = 0;
-    let header = loop {
-        read += client.read(&mut buffer[read..])?;
-
-        let header = HeaderResult::parse(&buffer[..read]);
-        if header.is_complete() {
-            break header;
-        }
+    let mut buffer = Vec::new();
+
+    // Read exactly as many bytes as the parser needs, so no bytes past the header are consumed.
+    while let Some(needed) = HeaderResult::parse(&buffer).bytes_needed() {
+        let read = buffer.len();
 
-        println!("Incomplete header. Read {} bytes so far.", read);
-    };
+        buffer.resize(read + needed, 0);
+        client.read_exact(&mut buffer[read..])?;
+
+        println!("Incomplete header. Read {} more bytes.", needed);
+    }
 
-    match header {
+    match HeaderResult::parse(&buffer) {
         HeaderResult::V1(Ok(header)) => println!("V1 Header: {}", header),
         HeaderResult::V2(Ok(header)) => println!("V2 Header: {}", header),
         HeaderResult::V1(Err(error)) => {
@@ -35,7 +34,7 @@ fn handle_connection(mut client: TcpStream) -> io::Result<()> {
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let address = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 7777);
-    let listener = TcpListener::bind(&address)?;
+    let listener = TcpListener::bind(address)?;
 
     println!("Server Listening on: {}", address);
 
@@ -0,0 +1,430 @@
+//! Conversions between version 1 and version 2 of the PROXY protocol.
+
+use crate::{v1, v2};
+#[cfg(feature = "std")]
+use crate::{HeaderResult, ParseOptions, PartialResult};
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+/// The size of the chunks read while looking for a complete header.
+#[cfg(feature = "std")]
+const READ_CHUNK: usize = 512;
+
+/// An error in converting a header from one version of the PROXY protocol to the other.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum ConversionError {
+    #[error("Header contains {0} bytes of TLVs that cannot be represented in version 1.")]
+    TLVs(usize),
+    #[error("Header contains Unix socket addresses that cannot be represented in version 1.")]
+    UnixAddresses,
+    #[error("Header protocol {0:?} cannot be represented in version 1, which only supports TCP.")]
+    Protocol(v2::Protocol),
+}
+
+impl v1::Addresses {
+    /// Converts the addresses of a version 2 header to version 1 addresses.
+    /// When `lossy` is true, any TLVs are dropped and addresses that cannot be represented in version 1 become `UNKNOWN`.
+    /// Otherwise, an error is returned when information would be lost.
+    ///
+    /// ## Examples
+    #[cfg_attr(feature = "std", doc = "```rust")]
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
+    /// use ppp::{v1, v2, ConversionError};
+    ///
+    /// let bytes = v2::Builder::with_addresses(
+    ///     v2::Version::Two | v2::Command::Proxy,
+    ///     v2::Protocol::Stream,
+    ///     v2::IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+    /// )
+    /// .write_tlv(v2::Type::NoOp, [42].as_slice())
+    /// .unwrap()
+    /// .build()
+    /// .unwrap();
+    /// let header = v2::Header::try_from(bytes.as_slice()).unwrap();
+    ///
+    /// assert_eq!(v1::Addresses::try_from(&header), Err(ConversionError::TLVs(4)));
+    /// assert_eq!(
+    ///     v1::Addresses::from_v2(&header, true).unwrap().to_string(),
+    ///     "PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\n"
+    /// );
+    /// ```
+    pub fn from_v2(header: &v2::Header, lossy: bool) -> Result<Self, ConversionError> {
+        let tlvs = header.tlv_bytes().len();
+
+        if tlvs > 0 && !lossy {
+            return Err(ConversionError::TLVs(tlvs));
+        }
+
+        if header.command == v2::Command::Local {
+            return Ok(v1::Addresses::Unknown);
+        }
+
+        let addresses = match (header.addresses, header.protocol) {
+            (v2::Addresses::Unspecified, _) => v1::Addresses::Unknown,
+            (v2::Addresses::IPv4(a), v2::Protocol::Stream) => v1::Addresses::Tcp4(a),
+            (v2::Addresses::IPv6(a), v2::Protocol::Stream) => v1::Addresses::Tcp6(a),
+            _ if lossy => v1::Addresses::Unknown,
+            (v2::Addresses::Unix(..), _) => return Err(ConversionError::UnixAddresses),
+            (_, protocol) => return Err(ConversionError::Protocol(protocol)),
+        };
+
+        Ok(addresses)
+    }
+}
+
+impl<'a, 'b> TryFrom<&'b v2::Header<'a>> for v1::Addresses {
+    type Error = ConversionError;
+
+    fn try_from(header: &'b v2::Header<'a>) -> Result<Self, Self::Error> {
+        v1::Addresses::from_v2(header, false)
+    }
+}
+
+impl From<v1::Addresses> for v2::Addresses {
+    fn from(addresses: v1::Addresses) -> Self {
+        match addresses {
+            v1::Addresses::Unknown => v2::Addresses::Unspecified,
+            v1::Addresses::Tcp4(a) => v2::Addresses::IPv4(a),
+            v1::Addresses::Tcp6(a) => v2::Addresses::IPv6(a),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, 'b> From<&'b v1::Header<'a>> for v2::Builder {
+    fn from(header: &'b v1::Header<'a>) -> Self {
+        let protocol = match header.addresses {
+            v1::Addresses::Unknown => v2::Protocol::Unspecified,
+            _ => v2::Protocol::Stream,
+        };
+
+        v2::Builder::with_addresses(
+            v2::Version::Two | v2::Command::Proxy,
+            protocol,
+            v2::Addresses::from(header.addresses),
+        )
+    }
+}
+
+/// The version of the PROXY protocol a `Translator` writes.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Target {
+    V1,
+    V2,
+}
+
+/// Rewrites the PROXY protocol header at the start of a stream to a given version and forwards the remainder of the stream unchanged.
+///
+/// ## Examples
+/// ```rust
+/// use ppp::Translator;
+///
+/// let mut input = "PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\nHello, World!".as_bytes();
+/// let mut output = Vec::new();
+///
+/// Translator::to_v2().translate(&mut input, &mut output).unwrap();
+///
+/// assert_eq!(output.len(), 28 + "Hello, World!".len());
+/// assert!(output.ends_with(b"Hello, World!"));
+/// ```
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Translator {
+    target: Target,
+    lossy: bool,
+    options: ParseOptions,
+}
+
+#[cfg(feature = "std")]
+impl Translator {
+    /// Creates a `Translator` that writes version 1 (text) headers.
+    pub fn to_v1() -> Self {
+        Translator {
+            target: Target::V1,
+            lossy: false,
+            options: ParseOptions::new(),
+        }
+    }
+
+    /// Creates a `Translator` that writes version 2 (binary) headers.
+    pub fn to_v2() -> Self {
+        Translator {
+            target: Target::V2,
+            lossy: false,
+            options: ParseOptions::new(),
+        }
+    }
+
+    /// Allows information that cannot be represented in the target version to be dropped.
+    /// See `v1::Addresses::from_v2` for details.
+    pub fn lossy(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
+    }
+
+    /// Sets the `ParseOptions` used to read the header from the reader.
+    pub fn options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Reads a PROXY protocol header of either version from the `reader`, writes the translated header to the `writer`,
+    /// and then copies the rest of the `reader` to the `writer`.
+    /// Returns the total number of bytes written.
+    ///
+    /// Headers that are already in the target version are forwarded as-is.
+    /// Invalid headers and headers that cannot be converted result in an `io::ErrorKind::InvalidData` error.
+    pub fn translate<R: Read + ?Sized, W: Write + ?Sized>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> io::Result<u64> {
+        let mut buffer = Vec::with_capacity(READ_CHUNK);
+
+        let (header, length) = loop {
+            let read = buffer.len();
+
+            buffer.resize(read + READ_CHUNK, 0);
+
+            let count = reader.read(&mut buffer[read..])?;
+
+            buffer.truncate(read + count);
+
+            let result = HeaderResult::parse_with(buffer.as_slice(), &self.options);
+
+            if result.is_complete() {
+                break self.translate_header(result)?;
+            }
+
+            if count == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        };
+
+        writer.write_all(header.as_slice())?;
+        writer.write_all(&buffer[length..])?;
+
+        let copied = io::copy(reader, writer)?;
+
+        Ok((header.len() + buffer.len() - length) as u64 + copied)
+    }
+
+    /// Translates a parsed header to the target version.
+    /// Returns the bytes of the translated header and the length of the original header.
+    fn translate_header(&self, result: HeaderResult) -> io::Result<(Vec<u8>, usize)> {
+        let invalid = |error: Box<dyn std::error::Error + Send + Sync>| {
+            io::Error::new(io::ErrorKind::InvalidData, error)
+        };
+
+        match (result, self.target) {
+            (HeaderResult::V1(Err(error)), _) => Err(invalid(error.into())),
+            (HeaderResult::V2(Err(error)), _) => Err(invalid(error.into())),
+            (HeaderResult::V1(Ok(header)), Target::V1) => {
+                Ok((header.header.as_bytes().to_vec(), header.header.len()))
+            }
+            (HeaderResult::V2(Ok(header)), Target::V2) => {
+                Ok((header.as_bytes().to_vec(), header.len()))
+            }
+            (HeaderResult::V1(Ok(header)), Target::V2) => {
+                Ok((v2::Builder::from(&header).build()?, header.header.len()))
+            }
+            (HeaderResult::V2(Ok(header)), Target::V1) => {
+                let addresses =
+                    v1::Addresses::from_v2(&header, self.lossy).map_err(|e| invalid(e.into()))?;
+
+                Ok((addresses.to_string().into_bytes(), header.len()))
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::v2::{Builder, Command, IPv4, IPv6, Protocol, Type, Unix, Version};
+
+    fn v2_header(builder: Builder) -> Vec<u8> {
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn v2_to_v1_tcp4() {
+        let bytes = v2_header(Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        ));
+        let header = v2::Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            v1::Addresses::try_from(&header),
+            Ok(v1::Addresses::new_tcp4(
+                [127, 0, 0, 1],
+                [192, 168, 1, 1],
+                80,
+                443
+            ))
+        );
+    }
+
+    #[test]
+    fn v2_to_v1_local() {
+        let bytes = v2_header(Builder::with_addresses(
+            Version::Two | Command::Local,
+            Protocol::Stream,
+            IPv6::new([0xFFFF; 8], [0xFFFE; 8], 80, 443),
+        ));
+        let header = v2::Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(v1::Addresses::try_from(&header), Ok(v1::Addresses::Unknown));
+    }
+
+    #[test]
+    fn v2_to_v1_unix() {
+        let bytes = v2_header(Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            Unix::new([0xFF; 108], [0xAA; 108]),
+        ));
+        let header = v2::Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            v1::Addresses::try_from(&header),
+            Err(ConversionError::UnixAddresses)
+        );
+        assert_eq!(
+            v1::Addresses::from_v2(&header, true),
+            Ok(v1::Addresses::Unknown)
+        );
+    }
+
+    #[test]
+    fn v2_to_v1_datagram() {
+        let bytes = v2_header(Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Datagram,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        ));
+        let header = v2::Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            v1::Addresses::try_from(&header),
+            Err(ConversionError::Protocol(Protocol::Datagram))
+        );
+        assert_eq!(
+            v1::Addresses::from_v2(&header, true),
+            Ok(v1::Addresses::Unknown)
+        );
+    }
+
+    #[test]
+    fn v1_to_v2() {
+        let header = v1::Header::try_from("PROXY TCP6 ::1 ::2 80 443\r\n").unwrap();
+        let expected = v2_header(Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv6::new([0, 0, 0, 0, 0, 0, 0, 1], [0, 0, 0, 0, 0, 0, 0, 2], 80, 443),
+        ));
+
+        assert_eq!(Builder::from(&header).build().unwrap(), expected);
+    }
+
+    #[test]
+    fn v1_unknown_to_v2() {
+        let header = v1::Header::try_from("PROXY UNKNOWN\r\n").unwrap();
+        let mut expected = Vec::from(v2::PROTOCOL_PREFIX);
+        expected.extend([0x21, 0x00, 0, 0]);
+
+        assert_eq!(Builder::from(&header).build().unwrap(), expected);
+    }
+
+    #[test]
+    fn translate_v2_to_v1() {
+        let mut input = v2_header(
+            Builder::with_addresses(
+                Version::Two | Command::Proxy,
+                Protocol::Stream,
+                IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+            )
+            .write_tlv(Type::NoOp, [42].as_slice())
+            .unwrap(),
+        );
+        input.extend_from_slice(b"Hello, World!");
+
+        let mut output = Vec::new();
+        let error = Translator::to_v1()
+            .translate(&mut input.as_slice(), &mut output)
+            .unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let mut output = Vec::new();
+        let written = Translator::to_v1()
+            .lossy(true)
+            .translate(&mut input.as_slice(), &mut output)
+            .unwrap();
+        let expected = b"PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\nHello, World!";
+
+        assert_eq!(output, expected);
+        assert_eq!(written, expected.len() as u64);
+    }
+
+    #[test]
+    fn translate_same_version() {
+        let input = b"PROXY UNKNOWN\r\nHello, World!";
+        let mut output = Vec::new();
+
+        Translator::to_v1()
+            .translate(&mut input.as_slice(), &mut output)
+            .unwrap();
+
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn translate_one_byte_at_a_time() {
+        struct OneByte<'a>(&'a [u8]);
+
+        impl<'a> Read for OneByte<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                match self.0.split_first() {
+                    Some((byte, rest)) if !buf.is_empty() => {
+                        buf[0] = *byte;
+                        self.0 = rest;
+                        Ok(1)
+                    }
+                    _ => Ok(0),
+                }
+            }
+        }
+
+        let input = b"PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\nHello";
+        let mut output = Vec::new();
+
+        Translator::to_v2()
+            .translate(&mut OneByte(input), &mut output)
+            .unwrap();
+
+        let header = v2::Header::try_from(output.as_slice()).unwrap();
+
+        assert_eq!(
+            header.addresses,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443).into()
+        );
+        assert_eq!(&output[header.len()..], b"Hello");
+    }
+
+    #[test]
+    fn translate_truncated() {
+        let input = b"PROXY TCP4 127.0.0.1";
+        let mut output = Vec::new();
+
+        let error = Translator::to_v2()
+            .translate(&mut input.as_slice(), &mut output)
+            .unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(output.is_empty());
+    }
+}
@@ -0,0 +1,324 @@
+//! A version-agnostic view of a PROXY protocol header.
+
+use crate::ip::{IPv4, IPv6};
+use crate::{v1, v2};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::net::SocketAddr;
+
+/// The transport protocol of the proxied connection.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Transport {
+    Unspecified,
+    Stream,
+    Datagram,
+}
+
+/// Common information carried by both versions of the PROXY protocol header.
+///
+/// ## Examples
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use ppp::{HeaderInfo, Transport, v1, v2};
+/// use std::net::SocketAddr;
+///
+/// fn client<H: HeaderInfo>(header: &H) -> Option<SocketAddr> {
+///     header.source()
+/// }
+///
+/// let text = v1::Header::try_from("PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\n").unwrap();
+/// let bytes = v2::Builder::with_addresses(
+///     v2::Version::Two | v2::Command::Proxy,
+///     v2::Protocol::Stream,
+///     ("127.0.0.1:80".parse::<SocketAddr>().unwrap(), "192.168.1.1:443".parse::<SocketAddr>().unwrap()),
+/// )
+/// .build()
+/// .unwrap();
+/// let binary = v2::Header::try_from(bytes.as_slice()).unwrap();
+///
+/// assert_eq!(client(&text), client(&binary));
+/// assert_eq!(text.transport(), Transport::Stream);
+/// assert_eq!(binary.transport(), Transport::Stream);
+/// ```
+pub trait HeaderInfo {
+    /// The address of the client that initiated the proxied connection.
+    /// `None` when the header carries no IP address information (i.e. `UNKNOWN`, `Unspecified`, `Unix` or a local connection).
+    fn source(&self) -> Option<SocketAddr>;
+
+    /// The address the client connected to on the proxy.
+    /// `None` when the header carries no IP address information (i.e. `UNKNOWN`, `Unspecified`, `Unix` or a local connection).
+    fn destination(&self) -> Option<SocketAddr>;
+
+    /// Tests whether the connection was established by the proxy itself (i.e. health checks).
+    /// The receiver should use the real connection endpoints for local connections.
+    fn is_local(&self) -> bool;
+
+    /// The transport protocol of the proxied connection.
+    fn transport(&self) -> Transport;
+
+    /// An `Iterator` of the `TypeLengthValue`s in the header.
+    /// Always empty for version 1 headers.
+    fn tlvs(&self) -> v2::TypeLengthValues<'_>;
+}
+
+/// An owned, version-agnostic PROXY protocol header.
+///
+/// ## Examples
+/// ```rust
+/// use ppp::{HeaderInfo, HeaderResult, ProxyHeader, Transport};
+///
+/// let header = match HeaderResult::parse(b"PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\n") {
+///     HeaderResult::V1(Ok(header)) => ProxyHeader::from(&header),
+///     HeaderResult::V2(Ok(header)) => ProxyHeader::from(&header),
+///     _ => panic!("invalid header"),
+/// };
+///
+/// assert_eq!(header.source(), Some("127.0.0.1:80".parse().unwrap()));
+/// assert_eq!(header.destination(), Some("192.168.1.1:443".parse().unwrap()));
+/// assert_eq!(header.transport(), Transport::Stream);
+/// assert!(!header.is_local());
+/// assert!(header.tlvs().is_empty());
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProxyHeader {
+    pub source: Option<SocketAddr>,
+    pub destination: Option<SocketAddr>,
+    pub local: bool,
+    pub transport: Transport,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::tlvs"))]
+    pub tlvs: Vec<u8>,
+}
+
+impl From<v2::Protocol> for Transport {
+    fn from(protocol: v2::Protocol) -> Self {
+        match protocol {
+            v2::Protocol::Unspecified => Transport::Unspecified,
+            v2::Protocol::Stream => Transport::Stream,
+            v2::Protocol::Datagram => Transport::Datagram,
+        }
+    }
+}
+
+impl IPv4 {
+    /// The source address and port as a `SocketAddr`.
+    pub fn source(&self) -> SocketAddr {
+        SocketAddr::from((self.source_address, self.source_port))
+    }
+
+    /// The destination address and port as a `SocketAddr`.
+    pub fn destination(&self) -> SocketAddr {
+        SocketAddr::from((self.destination_address, self.destination_port))
+    }
+}
+
+impl IPv6 {
+    /// The source address and port as a `SocketAddr`.
+    pub fn source(&self) -> SocketAddr {
+        SocketAddr::from((self.source_address, self.source_port))
+    }
+
+    /// The destination address and port as a `SocketAddr`.
+    pub fn destination(&self) -> SocketAddr {
+        SocketAddr::from((self.destination_address, self.destination_port))
+    }
+}
+
+impl<'a> HeaderInfo for v1::Header<'a> {
+    fn source(&self) -> Option<SocketAddr> {
+        match self.addresses {
+            v1::Addresses::Tcp4(a) => Some(a.source()),
+            v1::Addresses::Tcp6(a) => Some(a.source()),
+            v1::Addresses::Unknown => None,
+        }
+    }
+
+    fn destination(&self) -> Option<SocketAddr> {
+        match self.addresses {
+            v1::Addresses::Tcp4(a) => Some(a.destination()),
+            v1::Addresses::Tcp6(a) => Some(a.destination()),
+            v1::Addresses::Unknown => None,
+        }
+    }
+
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    fn transport(&self) -> Transport {
+        match self.addresses {
+            v1::Addresses::Unknown => Transport::Unspecified,
+            _ => Transport::Stream,
+        }
+    }
+
+    fn tlvs(&self) -> v2::TypeLengthValues<'_> {
+        v2::TypeLengthValues::from([].as_slice())
+    }
+}
+
+impl<'a> HeaderInfo for v2::Header<'a> {
+    fn source(&self) -> Option<SocketAddr> {
+        match self.addresses {
+            _ if self.is_local() => None,
+            v2::Addresses::IPv4(a) => Some(a.source()),
+            v2::Addresses::IPv6(a) => Some(a.source()),
+            v2::Addresses::Unix(..) | v2::Addresses::Unspecified => None,
+        }
+    }
+
+    fn destination(&self) -> Option<SocketAddr> {
+        match self.addresses {
+            _ if self.is_local() => None,
+            v2::Addresses::IPv4(a) => Some(a.destination()),
+            v2::Addresses::IPv6(a) => Some(a.destination()),
+            v2::Addresses::Unix(..) | v2::Addresses::Unspecified => None,
+        }
+    }
+
+    fn is_local(&self) -> bool {
+        self.command == v2::Command::Local
+    }
+
+    fn transport(&self) -> Transport {
+        self.protocol.into()
+    }
+
+    fn tlvs(&self) -> v2::TypeLengthValues<'_> {
+        v2::Header::tlvs(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl HeaderInfo for ProxyHeader {
+    fn source(&self) -> Option<SocketAddr> {
+        self.source
+    }
+
+    fn destination(&self) -> Option<SocketAddr> {
+        self.destination
+    }
+
+    fn is_local(&self) -> bool {
+        self.local
+    }
+
+    fn transport(&self) -> Transport {
+        self.transport
+    }
+
+    fn tlvs(&self) -> v2::TypeLengthValues<'_> {
+        v2::TypeLengthValues::from(self.tlvs.as_slice())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: HeaderInfo> From<&T> for ProxyHeader {
+    fn from(header: &T) -> Self {
+        ProxyHeader {
+            source: header.source(),
+            destination: header.destination(),
+            local: header.is_local(),
+            transport: header.transport(),
+            tlvs: header.tlvs().as_bytes().to_vec(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::v2::{Builder, Command, Protocol, Type, Unix, Version};
+
+    #[test]
+    fn v1_unknown() {
+        let header = v1::Header::try_from("PROXY UNKNOWN\r\n").unwrap();
+        let expected = ProxyHeader {
+            source: None,
+            destination: None,
+            local: false,
+            transport: Transport::Unspecified,
+            tlvs: vec![],
+        };
+
+        assert_eq!(ProxyHeader::from(&header), expected);
+        assert_eq!(header.tlvs().next(), None);
+    }
+
+    #[test]
+    fn v1_tcp6() {
+        let header = v1::Header::try_from("PROXY TCP6 ::1 ::2 80 443\r\n").unwrap();
+        let expected = ProxyHeader {
+            source: Some("[::1]:80".parse().unwrap()),
+            destination: Some("[::2]:443".parse().unwrap()),
+            local: false,
+            transport: Transport::Stream,
+            tlvs: vec![],
+        };
+
+        assert_eq!(ProxyHeader::from(&header), expected);
+    }
+
+    #[test]
+    fn v2_with_tlvs() {
+        let bytes = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Datagram,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .write_tlv(Type::NoOp, [42].as_slice())
+        .unwrap()
+        .build()
+        .unwrap();
+        let header = v2::Header::try_from(bytes.as_slice()).unwrap();
+        let expected = ProxyHeader {
+            source: Some("127.0.0.1:80".parse().unwrap()),
+            destination: Some("192.168.1.1:443".parse().unwrap()),
+            local: false,
+            transport: Transport::Datagram,
+            tlvs: vec![4, 0, 1, 42],
+        };
+        let actual = ProxyHeader::from(&header);
+
+        assert_eq!(actual, expected);
+        assert_eq!(
+            HeaderInfo::tlvs(&actual).collect::<Vec<_>>(),
+            header.tlvs().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn v2_local() {
+        let bytes = Builder::with_addresses(
+            Version::Two | Command::Local,
+            Protocol::Stream,
+            IPv6::new([0xFFFF; 8], [0xFFFE; 8], 80, 443),
+        )
+        .build()
+        .unwrap();
+        let header = v2::Header::try_from(bytes.as_slice()).unwrap();
+
+        assert!(header.is_local());
+        assert_eq!(header.source(), None);
+        assert_eq!(header.destination(), None);
+    }
+
+    #[test]
+    fn v2_unix() {
+        let bytes = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            Unix::new([0xFF; 108], [0xAA; 108]),
+        )
+        .build()
+        .unwrap();
+        let header = v2::Header::try_from(bytes.as_slice()).unwrap();
+
+        assert!(!header.is_local());
+        assert_eq!(header.source(), None);
+        assert_eq!(header.destination(), None);
+        assert_eq!(header.transport(), Transport::Stream);
+    }
+}
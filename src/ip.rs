@@ -1,9 +1,10 @@
 //! Models for storing IP v4 and v6 addresses and ports.
 
-use std::net::{Ipv4Addr, Ipv6Addr};
+use core::net::{Ipv4Addr, Ipv6Addr};
 
 /// The source and destination IPv4 addresses and TCP ports of a header.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IPv4 {
     pub source_address: Ipv4Addr,
     pub source_port: u16,
@@ -29,6 +30,7 @@ impl IPv4 {
 }
 /// The source and destination IPv6 addresses and TCP ports of a header.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IPv6 {
     pub source_address: Ipv6Addr,
     pub source_port: u16,
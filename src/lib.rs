@@ -1,11 +1,66 @@
 //! A Proxy Protocol Parser written in Rust.
 //! Supports both text and binary versions of the header protocol.
+//!
+//! ## Features
+//! - `std` (default): enables the `v2::Builder`, `v2::Writer`, `v2::HeaderTemplate`, `v2::HeaderMut`, `v2::StdIdSource` and `Translator`, which rely on `std::io`.
+//! - `alloc`: enables the owned types (i.e. `ProxyHeader` and `to_owned` on the headers) and the `lint` conformance checks.
+//! - `bytes`: parses headers from `bytes::Buf` into the `Bytes`-backed `v2::OwnedHeader` and builds headers into a `bytes::BufMut`.
+//! - `hmac`: signs headers with an HMAC-SHA256 signature TLV (`v2::Signer`) and verifies them (`v2::Verifier`).
+//! - `serde`: implements `Serialize` and `Deserialize` for the addresses, TLVs and owned headers.
+//!
+//! Parsing of both versions of the header works without either feature (`no_std`).
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+mod convert;
+mod header;
 mod ip;
+#[cfg(feature = "alloc")]
+mod lint;
+mod options;
+#[cfg(feature = "alloc")]
+mod scatter;
+#[cfg(feature = "serde")]
+mod serialize;
 
 pub mod v1;
 pub mod v2;
 
+pub use convert::ConversionError;
+#[cfg(feature = "std")]
+pub use convert::Translator;
+#[cfg(feature = "alloc")]
+pub use header::ProxyHeader;
+pub use header::{HeaderInfo, Transport};
+#[cfg(feature = "alloc")]
+pub use lint::{lint, Lint, Lintable, RECOMMENDED_MAX_LENGTH};
+pub use options::{ParseOptions, TlvPolicy};
+
+/// Data borrowed from the parsed input or, with the `alloc` feature, owned.
+/// With `alloc`, this is a `Cow`, as in previous releases.
+#[cfg(feature = "alloc")]
+pub type MaybeOwned<'a, T> = alloc::borrow::Cow<'a, T>;
+
+/// Data borrowed from the parsed input or, with the `alloc` feature, owned.
+/// Without `alloc`, only borrowed data can be held.
+#[cfg(not(feature = "alloc"))]
+pub type MaybeOwned<'a, T> = &'a T;
+
+/// Borrows the data as a `MaybeOwned`.
+#[cfg(feature = "alloc")]
+pub(crate) fn borrowed<T: ?Sized + alloc::borrow::ToOwned>(value: &T) -> MaybeOwned<'_, T> {
+    alloc::borrow::Cow::Borrowed(value)
+}
+
+/// Borrows the data as a `MaybeOwned`.
+#[cfg(not(feature = "alloc"))]
+pub(crate) fn borrowed<T: ?Sized>(value: &T) -> MaybeOwned<'_, T> {
+    value
+}
+
 /// The canonical way to determine when a streamed header should be retried in a streaming context.
 /// The protocol states that servers may choose to support partial headers or to close the connection if the header is not present all at once.
 pub trait PartialResult {
@@ -20,18 +75,42 @@ pub trait PartialResult {
     /// An action that leads to an incomplete result may have a different result with more bytes.
     /// Retrying with the same input will not change the result.
     fn is_incomplete(&self) -> bool;
+
+    /// The minimum number of additional bytes needed before retrying can change the result.
+    /// Always `Some` when this result is incomplete and `None` otherwise.
+    /// Readers can use this value to size exact reads without consuming any bytes past the header.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use ppp::{HeaderResult, PartialResult};
+    ///
+    /// assert_eq!(HeaderResult::parse(b"").bytes_needed(), Some(15));
+    /// assert_eq!(HeaderResult::parse(b"\r\n\r\n").bytes_needed(), Some(12));
+    /// assert_eq!(HeaderResult::parse(b"PROXY UNKNOWN\r").bytes_needed(), Some(1));
+    /// assert_eq!(HeaderResult::parse(b"PROXY UNKNOWN\r\n").bytes_needed(), None);
+    /// ```
+    fn bytes_needed(&self) -> Option<usize> {
+        None
+    }
 }
 
-impl<'a, T, E: PartialResult> PartialResult for Result<T, E> {
+impl<T, E: PartialResult> PartialResult for Result<T, E> {
     fn is_incomplete(&self) -> bool {
         match self {
             Ok(_) => false,
             Err(error) => error.is_incomplete(),
         }
     }
+
+    fn bytes_needed(&self) -> Option<usize> {
+        match self {
+            Ok(_) => None,
+            Err(error) => error.bytes_needed(),
+        }
+    }
 }
 
-impl<'a> PartialResult for v1::ParseError {
+impl PartialResult for v1::ParseError {
     fn is_incomplete(&self) -> bool {
         matches!(
             self,
@@ -45,24 +124,47 @@ impl<'a> PartialResult for v1::ParseError {
                 | v1::ParseError::MissingNewLine
         )
     }
+
+    fn bytes_needed(&self) -> Option<usize> {
+        match self {
+            v1::ParseError::MissingPrefix => Some(v1::MINIMUM_LENGTH),
+            _ if self.is_incomplete() => Some(1),
+            _ => None,
+        }
+    }
 }
 
-impl<'a> PartialResult for v1::BinaryParseError {
+impl PartialResult for v1::BinaryParseError {
     fn is_incomplete(&self) -> bool {
         match self {
             v1::BinaryParseError::Parse(error) => error.is_incomplete(),
             _ => false,
         }
     }
+
+    fn bytes_needed(&self) -> Option<usize> {
+        match self {
+            v1::BinaryParseError::Parse(error) => error.bytes_needed(),
+            _ => None,
+        }
+    }
 }
 
-impl<'a> PartialResult for v2::ParseError {
+impl PartialResult for v2::ParseError {
     fn is_incomplete(&self) -> bool {
         matches!(
             self,
             v2::ParseError::Incomplete(..) | v2::ParseError::Partial(..)
         )
     }
+
+    fn bytes_needed(&self) -> Option<usize> {
+        match self {
+            v2::ParseError::Incomplete(length) => Some(v2::MINIMUM_LENGTH - length),
+            v2::ParseError::Partial(length, expected) => Some(expected - length),
+            _ => None,
+        }
+    }
 }
 
 /// An enumeration of the supported header version's parse results.
@@ -103,18 +205,180 @@ impl<'a> PartialResult for HeaderResult<'a> {
             Self::V2(result) => result.is_incomplete(),
         }
     }
+
+    fn bytes_needed(&self) -> Option<usize> {
+        match self {
+            Self::V1(result) => result.bytes_needed(),
+            // An empty input may still turn out to be a shorter version 1 header.
+            Self::V2(Err(v2::ParseError::Incomplete(0))) => Some(v1::MINIMUM_LENGTH),
+            Self::V2(result) => result.bytes_needed(),
+        }
+    }
 }
 
 impl<'a> HeaderResult<'a> {
     /// Parses a PROXY protocol version 2 `Header`.
     /// If the input is not a valid version 2 `Header`, attempts to parse a version 1 `Header`.  
     pub fn parse(input: &'a [u8]) -> HeaderResult<'a> {
-        let header = v2::Header::try_from(input);
+        HeaderResult::parse_with(input, &ParseOptions::new())
+    }
 
-        if header.is_complete() && header.is_err() {
-            v1::Header::try_from(input).into()
+    /// Parses a PROXY protocol header with the given `ParseOptions`.
+    /// Only the enabled versions are attempted, version 2 first.
+    pub fn parse_with(input: &'a [u8], options: &ParseOptions) -> HeaderResult<'a> {
+        let header = v2::Header::parse_with(input, options);
+
+        if HeaderResult::should_try_v1(&header, options) {
+            v1::Header::parse_with(input, options).into()
+        } else {
+            header.into()
+        }
+    }
+
+    /// Parses a PROXY protocol header from a list of slices (i.e. `&[&[u8]]` or `&[IoSlice]`), as if the slices were contiguous.
+    /// Tries version 2 first, then version 1 like `HeaderResult::parse`.
+    /// The header borrows from the input when it is entirely contained in the first non-empty slice.
+    /// Otherwise, only the bytes of the header are copied into an owned header.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use ppp::{HeaderResult, v1};
+    ///
+    /// let slices = [b"PROXY TCP4 127.0.0.1 ".as_slice(), b"192.168.1.1 80 443\r\n"];
+    /// let header = HeaderResult::parse_slices(&slices);
+    ///
+    /// assert_eq!(
+    ///     header,
+    ///     Ok(v1::Header::new(
+    ///         "PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\n",
+    ///         v1::Addresses::new_tcp4([127, 0, 0, 1], [192, 168, 1, 1], 80, 443)
+    ///     )).into()
+    /// );
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn parse_slices<S: core::ops::Deref<Target = [u8]>>(slices: &'a [S]) -> HeaderResult<'a> {
+        HeaderResult::parse_slices_with(slices, &ParseOptions::new())
+    }
+
+    /// Parses a PROXY protocol header from a list of slices with the given `ParseOptions`.
+    /// See `parse_slices` for details.
+    #[cfg(feature = "alloc")]
+    pub fn parse_slices_with<S: core::ops::Deref<Target = [u8]>>(
+        slices: &'a [S],
+        options: &ParseOptions,
+    ) -> HeaderResult<'a> {
+        let header = v2::Header::parse_slices_with(slices, options);
+
+        if HeaderResult::should_try_v1(&header, options) {
+            v1::Header::parse_slices_with(slices, options).into()
         } else {
             header.into()
         }
     }
+
+    /// Tests whether the input should be parsed as a version 1 header given the result of parsing a version 2 header.
+    /// Headers that are valid version 2 headers, but are rejected by the options, are not parsed again.
+    fn should_try_v1(
+        header: &Result<v2::Header<'a>, v2::ParseError>,
+        options: &ParseOptions,
+    ) -> bool {
+        match header {
+            Err(
+                v2::ParseError::LocalCommand
+                | v2::ParseError::PayloadTooLong(..)
+                | v2::ParseError::MissingTLVs
+                | v2::ParseError::UnexpectedTLVs(..)
+                | v2::ParseError::MalformedTLV { .. }
+                | v2::ParseError::RejectedTLV { .. },
+            ) => false,
+            _ => options.v1 && header.is_complete() && header.is_err(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_needed_v1() {
+        let text = b"PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\n";
+
+        for length in 1..text.len() {
+            let result = v1::Header::try_from(&text[..length]);
+
+            assert_eq!(result.bytes_needed(), Some(1), "{:?}", result);
+        }
+
+        assert_eq!(v1::Header::try_from(text.as_slice()).bytes_needed(), None);
+        assert_eq!(
+            v1::Header::try_from(b"".as_slice()).bytes_needed(),
+            Some(15)
+        );
+        assert_eq!(
+            v1::Header::try_from(b"PROXY TCP5".as_slice()).bytes_needed(),
+            None
+        );
+    }
+
+    #[test]
+    fn bytes_needed_v2() {
+        let mut header = Vec::from(v2::PROTOCOL_PREFIX);
+        header.extend([0x21, 0x11, 0, 12, 127, 0, 0, 1, 127, 0, 0, 2, 0, 80, 1, 187]);
+
+        for length in 0..header.len() {
+            let result = v2::Header::try_from(&header[..length]);
+            let expected = if length < v2::MINIMUM_LENGTH {
+                v2::MINIMUM_LENGTH - length
+            } else {
+                header.len() - length
+            };
+
+            assert_eq!(result.bytes_needed(), Some(expected));
+        }
+
+        assert_eq!(v2::Header::try_from(header.as_slice()).bytes_needed(), None);
+        assert_eq!(v2::ParseError::Prefix.bytes_needed(), None);
+    }
+
+    #[test]
+    fn bytes_needed_header_result() {
+        assert_eq!(HeaderResult::parse(b"").bytes_needed(), Some(15));
+        assert_eq!(HeaderResult::parse(b"\r\n").bytes_needed(), Some(14));
+        assert_eq!(HeaderResult::parse(b"PROXY ").bytes_needed(), Some(1));
+        assert_eq!(HeaderResult::parse(b"GET /").bytes_needed(), None);
+    }
+
+    #[test]
+    fn parse_with_versions() {
+        let text = b"PROXY UNKNOWN\r\n";
+        let mut binary = Vec::from(v2::PROTOCOL_PREFIX);
+        binary.extend([0x20, 0x00, 0, 0]);
+        let options = ParseOptions::new();
+
+        assert!(matches!(
+            HeaderResult::parse_with(text, &options),
+            HeaderResult::V1(Ok(_))
+        ));
+        assert!(matches!(
+            HeaderResult::parse_with(text, &options.v2(false)),
+            HeaderResult::V1(Ok(_))
+        ));
+        assert_eq!(
+            HeaderResult::parse_with(text, &options.v1(false)),
+            HeaderResult::V2(Err(v2::ParseError::Prefix))
+        );
+        assert_eq!(
+            HeaderResult::parse_with(text, &options.v1(false).v2(false)),
+            HeaderResult::V2(Err(v2::ParseError::Disabled))
+        );
+        assert_eq!(
+            HeaderResult::parse_with(&binary, &options.v2(false)),
+            HeaderResult::V1(Err(v1::ParseError::InvalidPrefix.into()))
+        );
+        assert_eq!(
+            HeaderResult::parse_with(&binary, &options.allow_local(false)),
+            HeaderResult::V2(Err(v2::ParseError::LocalCommand))
+        );
+    }
 }
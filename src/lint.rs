@@ -0,0 +1,274 @@
+//! Checks for headers that parse successfully but do not follow the recommendations of the specification.
+
+use crate::{v1, v2, HeaderResult};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The largest header the specification recommends a sender emits,
+/// so that the header fits in the first segment of a TCP connection (the minimum TCP MSS).
+pub const RECOMMENDED_MAX_LENGTH: usize = 536;
+
+/// A warning about a header that is valid but does not follow the specification's recommendations.
+/// Receivers must still accept these headers; the warnings are meant to flag misbehaving senders.
+#[derive(Debug, PartialEq)]
+pub enum Lint {
+    /// A version 1 `UNKNOWN` header with text between the protocol and the new line.
+    UnknownWithAddresses,
+    /// A version 2 header with the `Local` command whose address family is not `Unspecified`.
+    LocalWithAddresses(v2::AddressFamily),
+    /// A version 2 header with the `Unspecified` address family and a non-empty address block of the given length.
+    UnspecifiedWithAddresses(usize),
+    /// A version 2 header that contains more than one TLV of the given type.
+    DuplicateTLV(u8),
+    /// A version 2 header whose `TypeLengthValue`s cannot be parsed.
+    InvalidTLVs(v2::ParseError),
+    /// A version 2 header of the given length that exceeds `RECOMMENDED_MAX_LENGTH`.
+    ExceedsRecommendedLength(usize),
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::UnknownWithAddresses => {
+                write!(f, "UNKNOWN header should not contain addresses.")
+            }
+            Lint::LocalWithAddresses(family) => write!(
+                f,
+                "Local command should use the Unspecified address family, not {:?}.",
+                family
+            ),
+            Lint::UnspecifiedWithAddresses(length) => write!(
+                f,
+                "Unspecified address family should not have an address block ({} bytes).",
+                length
+            ),
+            Lint::DuplicateTLV(kind) => write!(f, "TLV {:#X} appears more than once.", kind),
+            Lint::InvalidTLVs(error) => write!(f, "TLVs are malformed: {}", error),
+            Lint::ExceedsRecommendedLength(length) => write!(
+                f,
+                "Header length of {} bytes exceeds the recommended maximum of {} bytes.",
+                length, RECOMMENDED_MAX_LENGTH
+            ),
+        }
+    }
+}
+
+/// A header that can be checked for conformance with the specification.
+pub trait Lintable {
+    /// Appends a `Lint` for every recommendation this header does not follow.
+    fn lint_into(&self, lints: &mut Vec<Lint>);
+}
+
+/// Checks a successfully parsed header against the recommendations of the specification.
+/// Returns an empty `Vec` for conforming headers.
+///
+/// ## Examples
+/// ```rust
+/// use ppp::{lint, Lint, v1};
+///
+/// let conforming = v1::Header::try_from("PROXY UNKNOWN\r\n").unwrap();
+/// let trailing = v1::Header::try_from("PROXY UNKNOWN 127.0.0.1 192.168.1.1 80 443\r\n").unwrap();
+///
+/// assert!(lint(&conforming).is_empty());
+/// assert_eq!(lint(&trailing), vec![Lint::UnknownWithAddresses]);
+/// ```
+pub fn lint<H: Lintable + ?Sized>(header: &H) -> Vec<Lint> {
+    let mut lints = Vec::new();
+
+    header.lint_into(&mut lints);
+
+    lints
+}
+
+impl<'a> Lintable for v1::Header<'a> {
+    fn lint_into(&self, lints: &mut Vec<Lint>) {
+        if self.addresses == v1::Addresses::Unknown && !self.addresses_str().is_empty() {
+            lints.push(Lint::UnknownWithAddresses);
+        }
+    }
+}
+
+impl<'a> Lintable for v2::Header<'a> {
+    fn lint_into(&self, lints: &mut Vec<Lint>) {
+        let family = self.address_family();
+
+        if self.command == v2::Command::Local && family != v2::AddressFamily::Unspecified {
+            lints.push(Lint::LocalWithAddresses(family));
+        }
+
+        if family == v2::AddressFamily::Unspecified && !self.address_bytes().is_empty() {
+            lints.push(Lint::UnspecifiedWithAddresses(self.address_bytes().len()));
+        }
+
+        lint_tlvs(self.tlvs(), lints);
+
+        if self.len() > RECOMMENDED_MAX_LENGTH {
+            lints.push(Lint::ExceedsRecommendedLength(self.len()));
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl Lintable for v2::OwnedHeader {
+    fn lint_into(&self, lints: &mut Vec<Lint>) {
+        self.as_header().lint_into(lints)
+    }
+}
+
+/// Only successfully parsed headers are checked; errors produce no lints.
+impl<'a> Lintable for HeaderResult<'a> {
+    fn lint_into(&self, lints: &mut Vec<Lint>) {
+        match self {
+            HeaderResult::V1(Ok(header)) => header.lint_into(lints),
+            HeaderResult::V2(Ok(header)) => header.lint_into(lints),
+            _ => (),
+        }
+    }
+}
+
+/// Checks the `TypeLengthValue`s for duplicates.
+/// Multiple `NoOp`s are allowed, as padding may be split across several TLVs.
+/// The value of a `NoOp` is not checked, since the specification allows it to hold any data.
+fn lint_tlvs(tlvs: v2::TypeLengthValues<'_>, lints: &mut Vec<Lint>) {
+    let no_op = u8::from(v2::Type::NoOp);
+    let mut seen = [false; 256];
+    let mut reported = [false; 256];
+
+    for tlv in tlvs {
+        let tlv = match tlv {
+            Ok(tlv) => tlv,
+            Err(error) => {
+                lints.push(Lint::InvalidTLVs(error));
+                return;
+            }
+        };
+        let kind = tlv.kind as usize;
+
+        if tlv.kind != no_op && seen[kind] && !reported[kind] {
+            reported[kind] = true;
+            lints.push(Lint::DuplicateTLV(tlv.kind));
+        }
+
+        seen[kind] = true;
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::v2::{Builder, Command, IPv4, Protocol, Type, Version, PROTOCOL_PREFIX};
+
+    fn builder(command: Command) -> Builder {
+        Builder::with_addresses(
+            Version::Two | command,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+    }
+
+    #[test]
+    fn conforming() {
+        let input = builder(Command::Proxy)
+            .write_tlv(Type::Authority, b"example.com")
+            .unwrap()
+            .write_tlv(Type::NoOp, &[0; 4])
+            .unwrap()
+            .write_tlv(Type::NoOp, &[])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert!(lint(&v2::Header::try_from(input.as_slice()).unwrap()).is_empty());
+        assert!(lint(&HeaderResult::parse(input.as_slice())).is_empty());
+        assert!(lint(&HeaderResult::parse(
+            b"PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\n"
+        ))
+        .is_empty());
+        assert!(lint(&HeaderResult::parse(b"PROXY UNKNOWN\r\n")).is_empty());
+        assert!(lint(&HeaderResult::parse(b"PROXY UNKNOWN")).is_empty());
+    }
+
+    #[test]
+    fn local_with_addresses() {
+        let input = builder(Command::Local).build().unwrap();
+
+        assert_eq!(
+            lint(&HeaderResult::parse(input.as_slice())),
+            vec![Lint::LocalWithAddresses(v2::AddressFamily::IPv4)]
+        );
+    }
+
+    #[test]
+    fn unspecified_with_addresses() {
+        let mut input = Vec::from(PROTOCOL_PREFIX);
+        input.extend([0x21, 0x00, 0, 12]);
+        input.extend([127, 0, 0, 1, 127, 0, 0, 2, 0, 80, 1, 187]);
+
+        assert_eq!(
+            lint(&HeaderResult::parse(input.as_slice())),
+            vec![Lint::UnspecifiedWithAddresses(12)]
+        );
+    }
+
+    #[test]
+    fn tlvs() {
+        let input = builder(Command::Proxy)
+            .write_tlv(Type::Authority, b"a")
+            .unwrap()
+            .write_tlv(Type::NoOp, &[0, 1])
+            .unwrap()
+            .write_tlv(Type::NoOp, &[2])
+            .unwrap()
+            .write_tlv(Type::Authority, b"b")
+            .unwrap()
+            .write_tlv(Type::Authority, b"c")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            lint(&HeaderResult::parse(input.as_slice())),
+            vec![Lint::DuplicateTLV(Type::Authority.into())]
+        );
+    }
+
+    #[test]
+    fn invalid_tlvs() {
+        let mut input = Vec::from(PROTOCOL_PREFIX);
+        input.extend([0x21, 0x11, 0, 14]);
+        input.extend([127, 0, 0, 1, 127, 0, 0, 2, 0, 80, 1, 187]);
+        input.extend([0x02, 0]);
+
+        assert_eq!(
+            lint(&HeaderResult::parse(input.as_slice())),
+            vec![Lint::InvalidTLVs(v2::ParseError::Leftovers(2))]
+        );
+    }
+
+    #[test]
+    fn exceeds_recommended_length() {
+        let input = builder(Command::Proxy)
+            .write_tlv(Type::NoOp, &[0; 600])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            lint(&HeaderResult::parse(input.as_slice())),
+            vec![Lint::ExceedsRecommendedLength(input.len())]
+        );
+    }
+
+    #[test]
+    fn unknown_with_addresses() {
+        let lints = lint(&HeaderResult::parse(
+            b"PROXY UNKNOWN 127.0.0.1 192.168.1.1 80 443\r\n",
+        ));
+
+        assert_eq!(lints, vec![Lint::UnknownWithAddresses]);
+        assert_eq!(
+            lints[0].to_string(),
+            "UNKNOWN header should not contain addresses."
+        );
+    }
+}
@@ -0,0 +1,144 @@
+//! Options to configure the strictness and limits of the parsers.
+
+use crate::v2::TlvKind;
+
+/// Whether a version 2 header must, may or must not contain `TypeLengthValue`s.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum TlvPolicy {
+    /// TLVs are optional.
+    #[default]
+    Allow,
+    /// At least one TLV must be present.
+    Require,
+    /// No TLVs may be present.
+    Forbid,
+}
+
+/// Options that control which headers the parsers accept.
+/// The default options accept any header that follows the specification.
+///
+/// ## Examples
+/// ```rust
+/// use ppp::{HeaderResult, ParseOptions, v1};
+///
+/// const OPTIONS: ParseOptions = ParseOptions::new().v2(false).allow_v1_leading_zeros(true);
+/// let input = "PROXY TCP4 127.0.0.1 192.168.1.1 080 443\r\n";
+///
+/// assert_eq!(
+///     HeaderResult::parse(input.as_bytes()),
+///     HeaderResult::V1(Err(v1::ParseError::InvalidSourcePort(None).into()))
+/// );
+/// assert_eq!(
+///     HeaderResult::parse_with(input.as_bytes(), &OPTIONS),
+///     Ok(v1::Header::new(input, v1::Addresses::new_tcp4([127, 0, 0, 1], [192, 168, 1, 1], 80, 443))).into()
+/// );
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParseOptions {
+    pub(crate) v1: bool,
+    pub(crate) v2: bool,
+    pub(crate) max_v2_payload: u16,
+    pub(crate) tlvs: TlvPolicy,
+    pub(crate) allow_local: bool,
+    pub(crate) v1_leading_zeros: bool,
+    pub(crate) validate_tlvs: bool,
+    pub(crate) allow_unassigned_tlvs: bool,
+    pub(crate) allow_future_tlvs: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::new()
+    }
+}
+
+impl ParseOptions {
+    /// Creates the default `ParseOptions`.
+    pub const fn new() -> Self {
+        ParseOptions {
+            v1: true,
+            v2: true,
+            max_v2_payload: u16::MAX,
+            tlvs: TlvPolicy::Allow,
+            allow_local: true,
+            v1_leading_zeros: false,
+            validate_tlvs: false,
+            allow_unassigned_tlvs: true,
+            allow_future_tlvs: true,
+        }
+    }
+
+    /// Enables or disables parsing of version 1 (text) headers.
+    pub const fn v1(mut self, enabled: bool) -> Self {
+        self.v1 = enabled;
+        self
+    }
+
+    /// Enables or disables parsing of version 2 (binary) headers.
+    pub const fn v2(mut self, enabled: bool) -> Self {
+        self.v2 = enabled;
+        self
+    }
+
+    /// Sets the maximum length in bytes of a version 2 header's payload (addresses and TLVs).
+    /// Headers that advertise a longer payload are rejected before the payload is read.
+    pub const fn max_v2_payload(mut self, length: u16) -> Self {
+        self.max_v2_payload = length;
+        self
+    }
+
+    /// Sets whether version 2 headers must, may or must not contain `TypeLengthValue`s.
+    pub const fn tlvs(mut self, policy: TlvPolicy) -> Self {
+        self.tlvs = policy;
+        self
+    }
+
+    /// Allows or denies version 2 headers with the `Local` command.
+    pub const fn allow_local(mut self, allowed: bool) -> Self {
+        self.allow_local = allowed;
+        self
+    }
+
+    /// Allows or denies ports with leading zeros (e.g. `080`) in version 1 headers.
+    /// The specification does not allow them, but some senders emit them.
+    /// Version 2 headers are not affected.
+    pub const fn allow_v1_leading_zeros(mut self, allowed: bool) -> Self {
+        self.v1_leading_zeros = allowed;
+        self
+    }
+
+    /// Enables or disables validation of every version 2 `TypeLengthValue` (including those nested in `SSL`) while parsing.
+    /// When disabled, malformed `TypeLengthValue`s are only reported when iterating over them.
+    pub const fn validate_tlvs(mut self, enabled: bool) -> Self {
+        self.validate_tlvs = enabled;
+        self
+    }
+
+    /// Allows or rejects version 2 `TypeLengthValue`s with a type the specification has not assigned (see `v2::TlvKind`).
+    /// Rejecting any type requires every `TypeLengthValue` to be parsed, so malformed ones are rejected as well.
+    pub const fn allow_unassigned_tlvs(mut self, allowed: bool) -> Self {
+        self.allow_unassigned_tlvs = allowed;
+        self
+    }
+
+    /// Allows or rejects version 2 `TypeLengthValue`s with a type reserved for future use (see `v2::TlvKind`).
+    /// Rejecting any type requires every `TypeLengthValue` to be parsed, so malformed ones are rejected as well.
+    pub const fn allow_future_tlvs(mut self, allowed: bool) -> Self {
+        self.allow_future_tlvs = allowed;
+        self
+    }
+
+    /// Tests whether any `TypeLengthValue` must be parsed as part of the header.
+    pub(crate) fn walk_tlvs(&self) -> bool {
+        self.validate_tlvs || !self.allow_unassigned_tlvs || !self.allow_future_tlvs
+    }
+
+    /// Tests whether a `TypeLengthValue` of the given kind is allowed.
+    pub(crate) fn allows(&self, kind: TlvKind) -> bool {
+        match kind {
+            TlvKind::Unassigned(_) => self.allow_unassigned_tlvs,
+            TlvKind::Future(_) => self.allow_future_tlvs,
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Helpers for parsing headers from scattered buffers (i.e. vectored reads).
+
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+/// The first non-empty slice, or an empty slice if every slice is empty.
+pub(crate) fn first<S: Deref<Target = [u8]>>(slices: &[S]) -> &[u8] {
+    slices
+        .iter()
+        .map(Deref::deref)
+        .find(|slice| !slice.is_empty())
+        .unwrap_or_default()
+}
+
+/// The total number of bytes in the slices.
+pub(crate) fn total_len<S: Deref<Target = [u8]>>(slices: &[S]) -> usize {
+    slices.iter().map(|slice| slice.len()).sum()
+}
+
+/// Copies up to `limit` bytes from the start of the slices into a contiguous buffer.
+pub(crate) fn coalesce<S: Deref<Target = [u8]>>(slices: &[S], limit: usize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(limit.min(total_len(slices)));
+
+    for slice in slices {
+        let remaining = limit - bytes.len();
+
+        if remaining == 0 {
+            break;
+        }
+
+        bytes.extend_from_slice(&slice[..remaining.min(slice.len())]);
+    }
+
+    bytes
+}
@@ -0,0 +1,404 @@
+//! `Serialize` and `Deserialize` implementations that cannot be derived.
+//!
+//! Human-readable formats (i.e. JSON) get TLV types by name, UNIX addresses as paths and structured headers.
+//! Binary formats (i.e. bincode) get a compact form that round-trips every value exactly.
+
+use crate::v2::{Type, TypeLengthValue, TypeLengthValues, Unix};
+use crate::{v1, v2};
+use core::fmt;
+use serde::de::{self, Deserializer, IntoDeserializer, SeqAccess, Visitor};
+use serde::ser::{self, SerializeStruct, Serializer};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "alloc")]
+use crate::v2::{Addresses, Command, FixedBuilder, Protocol, Version, MINIMUM_LENGTH};
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec, vec::Vec};
+
+/// The length in bytes of a single UNIX socket address.
+const UNIX_ADDRESS_LENGTH: usize = 108;
+
+/// The type of a `TypeLengthValue`.
+/// Human-readable formats use the name of known types and the number of any other type.
+struct Kind(u8);
+
+impl Serialize for Kind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match Type::try_from(self.0).ok() {
+            Some(kind) if serializer.is_human_readable() => kind.serialize(serializer),
+            _ => serializer.serialize_u8(self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Kind {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KindVisitor;
+
+        impl<'de> Visitor<'de> for KindVisitor {
+            type Value = Kind;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a TLV type name or a byte")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                u8::try_from(value)
+                    .map(Kind)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                u8::try_from(value)
+                    .map(Kind)
+                    .map_err(|_| E::invalid_value(de::Unexpected::Signed(value), &self))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Type::deserialize(value.into_deserializer()).map(|kind| Kind(kind.into()))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(KindVisitor)
+        } else {
+            deserializer.deserialize_u8(KindVisitor)
+        }
+    }
+}
+
+/// Raw bytes, serialized with `serialize_bytes`.
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Serialize for Bytes<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self.0)
+    }
+}
+
+/// Owned raw bytes, deserialized from either bytes or a sequence of bytes.
+#[cfg(feature = "alloc")]
+struct ByteBuf(Vec<u8>);
+
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for ByteBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ByteBufVisitor;
+
+        impl<'de> Visitor<'de> for ByteBufVisitor {
+            type Value = ByteBuf;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a byte array")
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+                Ok(ByteBuf(value.to_vec()))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, value: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(ByteBuf(value))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+
+                Ok(ByteBuf(bytes))
+            }
+        }
+
+        deserializer.deserialize_byte_buf(ByteBufVisitor)
+    }
+}
+
+/// A single UNIX socket address.
+/// Human-readable formats use the path without the trailing NUL padding.
+/// Paths that are not valid UTF-8 fall back to bytes.
+struct UnixPath([u8; UNIX_ADDRESS_LENGTH]);
+
+impl Serialize for UnixPath {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_bytes(self.0.as_slice());
+        }
+
+        let length = self
+            .0
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |i| i + 1);
+        let path = &self.0[..length];
+
+        match core::str::from_utf8(path) {
+            Ok(path) => serializer.serialize_str(path),
+            Err(_) => serializer.serialize_bytes(path),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for UnixPath {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct UnixPathVisitor;
+
+        impl<'de> Visitor<'de> for UnixPathVisitor {
+            type Value = UnixPath;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "a path of at most {} bytes", UNIX_ADDRESS_LENGTH)
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                self.visit_bytes(value.as_bytes())
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Self::Value, E> {
+                let mut path = [0; UNIX_ADDRESS_LENGTH];
+
+                path.get_mut(..value.len())
+                    .ok_or_else(|| E::invalid_length(value.len(), &self))?
+                    .copy_from_slice(value);
+
+                Ok(UnixPath(path))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut path = [0; UNIX_ADDRESS_LENGTH];
+                let mut length = 0;
+
+                while let Some(byte) = seq.next_element()? {
+                    *path
+                        .get_mut(length)
+                        .ok_or_else(|| de::Error::invalid_length(length + 1, &self))? = byte;
+                    length += 1;
+                }
+
+                Ok(UnixPath(path))
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(UnixPathVisitor)
+        } else {
+            deserializer.deserialize_bytes(UnixPathVisitor)
+        }
+    }
+}
+
+impl Serialize for Unix {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Unix", 2)?;
+
+        state.serialize_field("source", &UnixPath(self.source))?;
+        state.serialize_field("destination", &UnixPath(self.destination))?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Unix {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Unix")]
+        struct Paths {
+            source: UnixPath,
+            destination: UnixPath,
+        }
+
+        let paths = Paths::deserialize(deserializer)?;
+
+        Ok(Unix::new(paths.source.0, paths.destination.0))
+    }
+}
+
+impl<'a> Serialize for TypeLengthValue<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("TypeLengthValue", 2)?;
+
+        state.serialize_field("kind", &Kind(self.kind))?;
+        state.serialize_field("value", &Bytes(self.value.as_ref()))?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for TypeLengthValue<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "TypeLengthValue")]
+        struct Owned {
+            kind: Kind,
+            value: ByteBuf,
+        }
+
+        let tlv = Owned::deserialize(deserializer)?;
+
+        Ok(TypeLengthValue {
+            kind: tlv.kind.0,
+            value: tlv.value.0.into(),
+        })
+    }
+}
+
+/// Serializes the value or fails with its error.
+struct Fallible<T, E>(Result<T, E>);
+
+impl<T: Serialize, E: fmt::Display> Serialize for Fallible<T, E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.0 {
+            Ok(value) => value.serialize(serializer),
+            Err(error) => Err(ser::Error::custom(error)),
+        }
+    }
+}
+
+/// The `TypeLengthValue`s of a header, serialized as a sequence.
+struct Sequence<'a>(TypeLengthValues<'a>);
+
+impl<'a> Serialize for Sequence<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.map(Fallible))
+    }
+}
+
+/// Encodes the `TypeLengthValue`s in their binary form.
+#[cfg(feature = "alloc")]
+fn encode_tlvs<E: de::Error>(tlvs: &[TypeLengthValue<'_>]) -> Result<Vec<u8>, E> {
+    let mut bytes = Vec::new();
+
+    for tlv in tlvs {
+        let length = u16::try_from(tlv.len())
+            .map_err(|_| E::custom(format_args!("TLV {:#04X} is too long", tlv.kind)))?;
+
+        bytes.push(tlv.kind);
+        bytes.extend_from_slice(length.to_be_bytes().as_slice());
+        bytes.extend_from_slice(tlv.value.as_ref());
+    }
+
+    Ok(bytes)
+}
+
+/// (De)serializes the binary `TypeLengthValue`s of a `ProxyHeader`.
+/// Human-readable formats use a sequence of `TypeLengthValue`s.
+#[cfg(feature = "alloc")]
+pub(crate) mod tlvs {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(tlvs: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            Sequence(TypeLengthValues::from(tlvs)).serialize(serializer)
+        } else {
+            serializer.serialize_bytes(tlvs)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            let tlvs = Vec::<TypeLengthValue<'static>>::deserialize(deserializer)?;
+
+            encode_tlvs(tlvs.as_slice())
+        } else {
+            ByteBuf::deserialize(deserializer).map(|bytes| bytes.0)
+        }
+    }
+}
+
+impl<'a> Serialize for v1::Header<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.header.as_ref())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for v1::Header<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<'a> Serialize for v2::Header<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_bytes(self.as_bytes());
+        }
+
+        let mut state = serializer.serialize_struct("Header", 6)?;
+
+        state.serialize_field("version", &self.version)?;
+        state.serialize_field("command", &self.command)?;
+        state.serialize_field("protocol", &self.protocol)?;
+        state.serialize_field("addresses", &self.addresses)?;
+
+        // Without addresses, the payload is not split into TLVs and is kept as raw bytes.
+        if self.address_family() == v2::AddressFamily::Unspecified {
+            state.serialize_field("payload", &Bytes(self.address_bytes()))?;
+        } else {
+            state.skip_field("payload")?;
+        }
+
+        state.serialize_field("tlvs", &Sequence(self.tlvs()))?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for v2::Header<'static> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "Header")]
+        struct Fields {
+            version: Version,
+            command: Command,
+            protocol: Protocol,
+            addresses: Addresses,
+            payload: Option<ByteBuf>,
+            tlvs: Vec<TypeLengthValue<'static>>,
+        }
+
+        if !deserializer.is_human_readable() {
+            return owned_header(ByteBuf::deserialize(deserializer)?.0);
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        let payload = fields.payload.map(|payload| payload.0).unwrap_or_default();
+        let tlvs = encode_tlvs(fields.tlvs.as_slice())?;
+        let buffer = vec![0; MINIMUM_LENGTH + fields.addresses.len() + payload.len() + tlvs.len()];
+        let mut builder = FixedBuilder::new(
+            buffer,
+            fields.version | fields.command,
+            fields.addresses.address_family() | fields.protocol,
+        )
+        .and_then(|builder| builder.write_addresses(&fields.addresses))
+        .and_then(|builder| builder.write_payload(payload.as_slice()))
+        .and_then(|builder| builder.write_payload(tlvs.as_slice()))
+        .map_err(de::Error::custom)?;
+
+        builder.build().map_err(de::Error::custom)?;
+
+        owned_header(builder.into_inner())
+    }
+}
+
+/// Parses the bytes as a header that takes ownership of them.
+#[cfg(feature = "alloc")]
+fn owned_header<E: de::Error>(bytes: Vec<u8>) -> Result<v2::Header<'static>, E> {
+    let header = v2::Header::try_from(bytes.as_slice()).map_err(E::custom)?;
+    let (version, command, protocol, addresses) = (
+        header.version,
+        header.command,
+        header.protocol,
+        header.addresses,
+    );
+
+    Ok(v2::Header {
+        header: bytes.into(),
+        version,
+        command,
+        protocol,
+        addresses,
+    })
+}
@@ -0,0 +1,378 @@
+//! Allocation-free serialization of text PROXY protocol headers into caller-provided buffers.
+
+use crate::v1::{Addresses, WriteError, MAX_LENGTH, PROTOCOL_PREFIX, PROTOCOL_SUFFIX, SEPARATOR};
+use core::net::{Ipv4Addr, Ipv6Addr};
+
+/// The lowercase hexadecimal digits.
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+impl Addresses {
+    /// The length in bytes of the text header for this `Addresses`, including the trailing "\r\n".
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use std::net::Ipv4Addr;
+    /// use ppp::v1::Addresses;
+    ///
+    /// let addresses = Addresses::new_tcp4(Ipv4Addr::new(127, 0, 1, 2), Ipv4Addr::new(192, 168, 1, 101), 80, 443);
+    ///
+    /// assert_eq!(addresses.encoded_len(), addresses.to_string().len());
+    /// ```
+    pub fn encoded_len(&self) -> usize {
+        let addresses = match self {
+            Addresses::Unknown => 0,
+            Addresses::Tcp4(a) => {
+                ipv4_len(&a.source_address)
+                    + ipv4_len(&a.destination_address)
+                    + decimal_len(a.source_port)
+                    + decimal_len(a.destination_port)
+                    + 4
+            }
+            Addresses::Tcp6(a) => {
+                ipv6_len(&a.source_address)
+                    + ipv6_len(&a.destination_address)
+                    + decimal_len(a.source_port)
+                    + decimal_len(a.destination_port)
+                    + 4
+            }
+        };
+
+        PROTOCOL_PREFIX.len() + 1 + self.protocol().len() + addresses + PROTOCOL_SUFFIX.len()
+    }
+
+    /// Writes the text header for this `Addresses` to the start of the given buffer without allocating.
+    /// Returns the number of bytes written, or an error if the buffer is smaller than `encoded_len`.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use std::net::Ipv4Addr;
+    /// use ppp::v1::Addresses;
+    ///
+    /// let addresses = Addresses::new_tcp4(Ipv4Addr::new(127, 0, 1, 2), Ipv4Addr::new(192, 168, 1, 101), 80, 443);
+    /// let mut buffer = [0; 107];
+    ///
+    /// let length = addresses.write_to(&mut buffer).unwrap();
+    ///
+    /// assert_eq!(&buffer[..length], b"PROXY TCP4 127.0.1.2 192.168.1.101 80 443\r\n");
+    /// ```
+    pub fn write_to(&self, buffer: &mut [u8]) -> Result<usize, WriteError> {
+        // Every header fits in the maximum length, so only smaller buffers need to be measured.
+        if buffer.len() < MAX_LENGTH {
+            let length = self.encoded_len();
+
+            if buffer.len() < length {
+                return Err(WriteError::BufferTooSmall(buffer.len(), length));
+            }
+        }
+
+        let mut offset = write_str(buffer, PROTOCOL_PREFIX);
+
+        offset += write_separator(&mut buffer[offset..]);
+        offset += write_str(&mut buffer[offset..], self.protocol());
+
+        match self {
+            Addresses::Unknown => (),
+            Addresses::Tcp4(a) => {
+                offset += write_separator(&mut buffer[offset..]);
+                offset += write_ipv4(&mut buffer[offset..], &a.source_address);
+                offset += write_separator(&mut buffer[offset..]);
+                offset += write_ipv4(&mut buffer[offset..], &a.destination_address);
+                offset += write_separator(&mut buffer[offset..]);
+                offset += write_decimal(&mut buffer[offset..], a.source_port);
+                offset += write_separator(&mut buffer[offset..]);
+                offset += write_decimal(&mut buffer[offset..], a.destination_port);
+            }
+            Addresses::Tcp6(a) => {
+                offset += write_separator(&mut buffer[offset..]);
+                offset += write_ipv6(&mut buffer[offset..], &a.source_address);
+                offset += write_separator(&mut buffer[offset..]);
+                offset += write_ipv6(&mut buffer[offset..], &a.destination_address);
+                offset += write_separator(&mut buffer[offset..]);
+                offset += write_decimal(&mut buffer[offset..], a.source_port);
+                offset += write_separator(&mut buffer[offset..]);
+                offset += write_decimal(&mut buffer[offset..], a.destination_port);
+            }
+        }
+
+        offset += write_str(&mut buffer[offset..], PROTOCOL_SUFFIX);
+
+        Ok(offset)
+    }
+
+    /// Writes the text header to a stack buffer and passes the resulting string to the given function.
+    pub(crate) fn with_str<T>(&self, f: impl FnOnce(&str) -> T) -> T {
+        let mut buffer = [0; MAX_LENGTH];
+        let length = self
+            .write_to(&mut buffer)
+            .expect("a text header always fits in the maximum header length");
+
+        f(core::str::from_utf8(&buffer[..length]).expect("a text header is always ASCII"))
+    }
+}
+
+/// Copies the string to the start of the buffer.
+fn write_str(buffer: &mut [u8], value: &str) -> usize {
+    buffer[..value.len()].copy_from_slice(value.as_bytes());
+    value.len()
+}
+
+/// Writes the header part separator to the start of the buffer.
+fn write_separator(buffer: &mut [u8]) -> usize {
+    buffer[0] = SEPARATOR as u8;
+    1
+}
+
+/// The number of decimal digits required to represent the value.
+fn decimal_len(value: u16) -> usize {
+    match value {
+        0..=9 => 1,
+        10..=99 => 2,
+        100..=999 => 3,
+        1000..=9999 => 4,
+        _ => 5,
+    }
+}
+
+/// Writes the decimal representation of the value to the start of the buffer, without leading zeroes.
+fn write_decimal(buffer: &mut [u8], value: u16) -> usize {
+    let length = decimal_len(value);
+    let mut value = value;
+
+    for digit in buffer[..length].iter_mut().rev() {
+        *digit = b'0' + (value % 10) as u8;
+        value /= 10;
+    }
+
+    length
+}
+
+/// The number of lowercase hexadecimal digits required to represent the value.
+fn hex_len(value: u16) -> usize {
+    match value {
+        0..=0xF => 1,
+        0x10..=0xFF => 2,
+        0x100..=0xFFF => 3,
+        _ => 4,
+    }
+}
+
+/// Writes the lowercase hexadecimal representation of the value to the start of the buffer, without leading zeroes.
+fn write_hex(buffer: &mut [u8], value: u16) -> usize {
+    let length = hex_len(value);
+    let mut value = value;
+
+    for digit in buffer[..length].iter_mut().rev() {
+        *digit = HEX_DIGITS[(value & 0xF) as usize];
+        value >>= 4;
+    }
+
+    length
+}
+
+/// The length of the dotted-decimal representation of the address.
+fn ipv4_len(address: &Ipv4Addr) -> usize {
+    address
+        .octets()
+        .iter()
+        .map(|&octet| decimal_len(octet as u16))
+        .sum::<usize>()
+        + 3
+}
+
+/// Writes the dotted-decimal representation of the address to the start of the buffer.
+fn write_ipv4(buffer: &mut [u8], address: &Ipv4Addr) -> usize {
+    let mut offset = 0;
+
+    for (index, octet) in address.octets().into_iter().enumerate() {
+        if index > 0 {
+            buffer[offset] = b'.';
+            offset += 1;
+        }
+
+        offset += write_decimal(&mut buffer[offset..], octet as u16);
+    }
+
+    offset
+}
+
+/// Finds the start and length of the first longest run of zero segments.
+/// Runs of a single segment are not compressed, so a length of 0 is returned for them.
+fn longest_zero_run(segments: &[u16; 8]) -> (usize, usize) {
+    let mut longest = (0, 0);
+    let mut current = (0, 0);
+
+    for (index, &segment) in segments.iter().enumerate() {
+        if segment == 0 {
+            if current.1 == 0 {
+                current.0 = index;
+            }
+
+            current.1 += 1;
+
+            if current.1 > longest.1 {
+                longest = current;
+            }
+        } else {
+            current = (0, 0);
+        }
+    }
+
+    if longest.1 > 1 {
+        longest
+    } else {
+        (0, 0)
+    }
+}
+
+/// The length of the colon-separated hexadecimal representation of the segments.
+fn segments_len(segments: &[u16]) -> usize {
+    let digits: usize = segments.iter().map(|&segment| hex_len(segment)).sum();
+
+    digits + segments.len().saturating_sub(1)
+}
+
+/// Writes the colon-separated hexadecimal representation of the segments to the start of the buffer.
+fn write_segments(buffer: &mut [u8], segments: &[u16]) -> usize {
+    let mut offset = 0;
+
+    for (index, &segment) in segments.iter().enumerate() {
+        if index > 0 {
+            buffer[offset] = b':';
+            offset += 1;
+        }
+
+        offset += write_hex(&mut buffer[offset..], segment);
+    }
+
+    offset
+}
+
+/// The length of the representation of the address, matching the output of `Display` for `Ipv6Addr`.
+fn ipv6_len(address: &Ipv6Addr) -> usize {
+    if let Some(address) = address.to_ipv4_mapped() {
+        return "::ffff:".len() + ipv4_len(&address);
+    }
+
+    let segments = address.segments();
+
+    match longest_zero_run(&segments) {
+        (_, 0) => segments_len(&segments),
+        (start, length) => {
+            segments_len(&segments[..start]) + 2 + segments_len(&segments[start + length..])
+        }
+    }
+}
+
+/// Writes the representation of the address to the start of the buffer, matching the output of `Display` for `Ipv6Addr`.
+/// The longest run of zero segments is compressed and IPv4-mapped addresses use the dotted-decimal notation.
+fn write_ipv6(buffer: &mut [u8], address: &Ipv6Addr) -> usize {
+    if let Some(address) = address.to_ipv4_mapped() {
+        let offset = write_str(buffer, "::ffff:");
+
+        return offset + write_ipv4(&mut buffer[offset..], &address);
+    }
+
+    let segments = address.segments();
+
+    match longest_zero_run(&segments) {
+        (_, 0) => write_segments(buffer, &segments),
+        (start, length) => {
+            let mut offset = write_segments(buffer, &segments[..start]);
+
+            offset += write_str(&mut buffer[offset..], "::");
+            offset + write_segments(&mut buffer[offset..], &segments[start + length..])
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    fn assert_encoded(addresses: Addresses) {
+        let expected = match addresses {
+            Addresses::Unknown => "PROXY UNKNOWN\r\n".to_string(),
+            Addresses::Tcp4(a) => format!(
+                "PROXY TCP4 {} {} {} {}\r\n",
+                a.source_address, a.destination_address, a.source_port, a.destination_port
+            ),
+            Addresses::Tcp6(a) => format!(
+                "PROXY TCP6 {} {} {} {}\r\n",
+                a.source_address, a.destination_address, a.source_port, a.destination_port
+            ),
+        };
+        let mut buffer = [0xFF; MAX_LENGTH];
+        let length = addresses.write_to(&mut buffer).unwrap();
+
+        assert_eq!(addresses.encoded_len(), expected.len());
+        assert_eq!(std::str::from_utf8(&buffer[..length]).unwrap(), expected);
+        assert_eq!(addresses.to_string(), expected);
+    }
+
+    #[test]
+    fn encode_unknown() {
+        assert_encoded(Addresses::Unknown);
+    }
+
+    #[test]
+    fn encode_tcp4() {
+        assert_encoded(Addresses::new_tcp4(
+            [0, 0, 0, 0],
+            [255, 255, 255, 255],
+            0,
+            65535,
+        ));
+        assert_encoded(Addresses::new_tcp4(
+            [10, 9, 100, 99],
+            [1, 20, 200, 0],
+            9,
+            10,
+        ));
+        assert_encoded(Addresses::new_tcp4(
+            [127, 0, 1, 2],
+            [192, 168, 1, 101],
+            999,
+            1000,
+        ));
+    }
+
+    #[test]
+    fn encode_tcp6() {
+        let addresses = [
+            "::",
+            "::1",
+            "1::",
+            "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+            "1:0:2:0:3:0:4:0",
+            "1:0:0:2:0:0:0:3",
+            "1:0:0:2:0:0:3:4",
+            "0:0:1:0:0:0:0:0",
+            "fe80::a:bc:def:1234",
+            "::ffff:192.168.1.1",
+            "::192.168.1.1",
+            "64:ff9b::10.0.0.1",
+        ];
+
+        for (index, address) in addresses.iter().enumerate() {
+            let address: Ipv6Addr = address.parse().unwrap();
+
+            assert_encoded(Addresses::new_tcp6(
+                address,
+                Ipv6Addr::LOCALHOST,
+                index as u16,
+                u16::MAX - index as u16,
+            ));
+        }
+    }
+
+    #[test]
+    fn encode_buffer_too_small() {
+        let addresses = Addresses::new_tcp4([127, 0, 0, 1], [127, 0, 0, 2], 80, 443);
+        let mut buffer = [0; 16];
+
+        assert_eq!(
+            addresses.write_to(&mut buffer),
+            Err(WriteError::BufferTooSmall(16, addresses.encoded_len()))
+        );
+    }
+}
@@ -2,6 +2,7 @@
 
 /// An error in parsing a text PROXY protocol header.
 #[derive(thiserror::Error, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum ParseError {
     #[error("Header must start with 'PROXY'.")]
     InvalidPrefix,
@@ -28,13 +29,15 @@ pub enum ParseError {
     #[error("Header must end in '\r\n'.")]
     InvalidSuffix,
     #[error("Header contains invalid IP address for the source.")]
-    InvalidSourceAddress(#[source] std::net::AddrParseError),
+    InvalidSourceAddress(#[source] core::net::AddrParseError),
     #[error("Header contains invalid IP address for the destination.")]
-    InvalidDestinationAddress(#[source] std::net::AddrParseError),
+    InvalidDestinationAddress(#[source] core::net::AddrParseError),
     #[error("Header contains invalid TCP port for the source.")]
-    InvalidSourcePort(#[source] Option<std::num::ParseIntError>),
+    InvalidSourcePort(#[source] Option<core::num::ParseIntError>),
     #[error("Header contains invalid TCP port for the destination.")]
-    InvalidDestinationPort(#[source] Option<std::num::ParseIntError>),
+    InvalidDestinationPort(#[source] Option<core::num::ParseIntError>),
+    #[error("Version 1 headers are disabled by the parse options.")]
+    Disabled,
 }
 
 /// An error in parsing a text PROXY protocol header that is represented as a byte slice.
@@ -43,5 +46,12 @@ pub enum BinaryParseError {
     #[error(transparent)]
     Parse(#[from] ParseError),
     #[error("Header is not valid UTF-8.")]
-    InvalidUtf8(#[from] std::str::Utf8Error),
+    InvalidUtf8(#[from] core::str::Utf8Error),
+}
+
+/// An error in writing a text PROXY protocol header to a caller-provided buffer.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum WriteError {
+    #[error("Buffer of {0} bytes cannot hold the {1} bytes of the header.")]
+    BufferTooSmall(usize, usize),
 }
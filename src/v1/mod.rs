@@ -2,17 +2,21 @@
 //!
 //! See <https://haproxy.org/download/1.8/doc/proxy-protocol.txt>
 
+mod encode;
 mod error;
 mod model;
 
 pub use crate::ip::{IPv4, IPv6};
-pub use error::{BinaryParseError, ParseError};
+use crate::ParseOptions;
+#[cfg(feature = "alloc")]
+use crate::{scatter, PartialResult};
+use core::cmp::min;
+use core::iter::Peekable;
+use core::net::{AddrParseError, Ipv4Addr, Ipv6Addr};
+use core::str::{from_utf8, FromStr};
+pub use error::{BinaryParseError, ParseError, WriteError};
 pub use model::{Addresses, Header, SEPARATOR, TCP4, TCP6, UNKNOWN};
 pub use model::{PROTOCOL_PREFIX, PROTOCOL_SUFFIX};
-use std::borrow::Cow;
-use std::cmp::min;
-use std::net::{AddrParseError, Ipv4Addr, Ipv6Addr};
-use std::str::{from_utf8, FromStr};
 
 const ZERO: &str = "0";
 const NEWLINE: &str = "\n";
@@ -20,12 +24,15 @@ const CARRIAGE_RETURN: char = '\r';
 
 /// The maximum length of a header in bytes.
 const MAX_LENGTH: usize = 107;
+/// The length in bytes of the shortest possible header (i.e. `PROXY UNKNOWN\r\n`).
+pub(crate) const MINIMUM_LENGTH: usize =
+    PROTOCOL_PREFIX.len() + SEPARATOR.len_utf8() + UNKNOWN.len() + PROTOCOL_SUFFIX.len();
 /// The total number of parts in the header.
 const PARTS: usize = 7;
 
 /// Parses a text PROXY protocol header.
 /// The given string is expected to only include the header and to end in \r\n.
-fn parse_header(header: &str) -> Result<Header, ParseError> {
+fn parse_header(header: &str, leading_zeros: bool) -> Result<Header<'_>, ParseError> {
     if header.is_empty() {
         return Err(ParseError::MissingPrefix);
     } else if header.len() > MAX_LENGTH {
@@ -33,7 +40,7 @@ fn parse_header(header: &str) -> Result<Header, ParseError> {
     }
 
     let mut iterator = header
-        .splitn(PARTS, |c| c == SEPARATOR || c == CARRIAGE_RETURN)
+        .splitn(PARTS, [SEPARATOR, CARRIAGE_RETURN])
         .peekable();
 
     let prefix = iterator.next().ok_or(ParseError::MissingPrefix)?;
@@ -47,7 +54,7 @@ fn parse_header(header: &str) -> Result<Header, ParseError> {
     let addresses = match iterator.next() {
         Some(TCP4) => {
             let (source_address, destination_address, source_port, destination_port) =
-                parse_addresses::<Ipv4Addr, _>(&mut iterator)?;
+                parse_addresses::<Ipv4Addr, _>(&mut iterator, leading_zeros)?;
 
             Addresses::Tcp4(IPv4 {
                 source_address,
@@ -58,7 +65,7 @@ fn parse_header(header: &str) -> Result<Header, ParseError> {
         }
         Some(TCP6) => {
             let (source_address, destination_address, source_port, destination_port) =
-                parse_addresses::<Ipv6Addr, _>(&mut iterator)?;
+                parse_addresses::<Ipv6Addr, _>(&mut iterator, leading_zeros)?;
 
             Addresses::Tcp6(IPv6 {
                 source_address,
@@ -96,21 +103,26 @@ fn parse_header(header: &str) -> Result<Header, ParseError> {
     }
 
     Ok(Header {
-        header: Cow::Borrowed(header),
+        header: crate::borrowed(header),
         addresses,
     })
 }
 
 /// Parses the addresses and ports from a PROXY protocol header for IPv4 and IPv6.
+/// Ports with leading zeros are only accepted when `leading_zeros` is set.
 fn parse_addresses<'a, T: FromStr<Err = AddrParseError>, I: Iterator<Item = &'a str>>(
-    iterator: &mut I,
+    iterator: &mut Peekable<I>,
+    leading_zeros: bool,
 ) -> Result<(T, T, u16, u16), ParseError> {
     let source_address = iterator.next().ok_or(ParseError::MissingSourceAddress)?;
     let destination_address = iterator
         .next()
         .ok_or(ParseError::MissingDestinationAddress)?;
     let source_port = iterator.next().ok_or(ParseError::MissingSourcePort)?;
-    let destination_port = iterator.next().ok_or(ParseError::MissingDestinationPort)?;
+    let destination_port = iterator
+        .next()
+        .filter(|port| !port.is_empty() || iterator.peek().is_some())
+        .ok_or(ParseError::MissingDestinationPort)?;
 
     let source_address = source_address
         .parse::<T>()
@@ -119,7 +131,7 @@ fn parse_addresses<'a, T: FromStr<Err = AddrParseError>, I: Iterator<Item = &'a
         .parse::<T>()
         .map_err(ParseError::InvalidDestinationAddress)?;
 
-    if source_port.starts_with(ZERO) && source_port != ZERO {
+    if !leading_zeros && source_port.starts_with(ZERO) && source_port != ZERO {
         return Err(ParseError::InvalidSourcePort(None));
     }
 
@@ -127,7 +139,7 @@ fn parse_addresses<'a, T: FromStr<Err = AddrParseError>, I: Iterator<Item = &'a
         .parse::<u16>()
         .map_err(|e| ParseError::InvalidSourcePort(Some(e)))?;
 
-    if destination_port.starts_with(ZERO) && destination_port != ZERO {
+    if !leading_zeros && destination_port.starts_with(ZERO) && destination_port != ZERO {
         return Err(ParseError::InvalidDestinationPort(None));
     }
 
@@ -147,13 +159,7 @@ impl<'a> TryFrom<&'a str> for Header<'a> {
     type Error = ParseError;
 
     fn try_from(input: &'a str) -> Result<Self, Self::Error> {
-        let length = match input.find(CARRIAGE_RETURN) {
-            Some(suffix) => min(suffix + PROTOCOL_SUFFIX.len(), input.len()),
-            None if input.len() >= MAX_LENGTH => return Err(ParseError::HeaderTooLong),
-            None => input.len(),
-        };
-
-        parse_header(&input[..length])
+        Header::parse_str_with(input, &ParseOptions::new())
     }
 }
 
@@ -161,6 +167,43 @@ impl<'a> TryFrom<&'a [u8]> for Header<'a> {
     type Error = BinaryParseError;
 
     fn try_from(input: &'a [u8]) -> Result<Self, Self::Error> {
+        Header::parse_with(input, &ParseOptions::new())
+    }
+}
+
+impl<'a> Header<'a> {
+    /// Parses a header from the start of the string with the given `ParseOptions`.
+    pub fn parse_str_with(input: &'a str, options: &ParseOptions) -> Result<Self, ParseError> {
+        if !options.v1 {
+            return Err(ParseError::Disabled);
+        }
+
+        let length = match input.find(CARRIAGE_RETURN) {
+            Some(suffix) => min(suffix + PROTOCOL_SUFFIX.len(), input.len()),
+            None if input.len() >= MAX_LENGTH => return Err(ParseError::HeaderTooLong),
+            None => input.len(),
+        };
+
+        parse_header(&input[..length], options.v1_leading_zeros)
+    }
+
+    /// Parses a header from the start of the bytes with the given `ParseOptions`.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use ppp::{ParseOptions, v1};
+    ///
+    /// let input = b"PROXY TCP4 127.0.0.1 192.168.1.1 0080 443\r\n";
+    /// let lenient = ParseOptions::default().allow_v1_leading_zeros(true);
+    ///
+    /// assert!(v1::Header::try_from(input.as_slice()).is_err());
+    /// assert_eq!(v1::Header::parse_with(input, &lenient).unwrap().addresses, v1::Addresses::new_tcp4([127, 0, 0, 1], [192, 168, 1, 1], 80, 443));
+    /// ```
+    pub fn parse_with(input: &'a [u8], options: &ParseOptions) -> Result<Self, BinaryParseError> {
+        if !options.v1 {
+            return Err(ParseError::Disabled.into());
+        }
+
         let length = match input.iter().position(|&c| CARRIAGE_RETURN == (c as char)) {
             Some(suffix) => min(suffix + PROTOCOL_SUFFIX.len(), input.len()),
             None if input.len() >= MAX_LENGTH => return Err(ParseError::HeaderTooLong.into()),
@@ -168,7 +211,47 @@ impl<'a> TryFrom<&'a [u8]> for Header<'a> {
         };
         let header = from_utf8(&input[..length])?;
 
-        parse_header(header).map_err(BinaryParseError::Parse)
+        parse_header(header, options.v1_leading_zeros).map_err(BinaryParseError::Parse)
+    }
+
+    /// Parses a header from a list of slices (i.e. `&[&[u8]]` or `&[IoSlice]`), as if the slices were contiguous.
+    /// The header borrows from the input when it is entirely contained in the first non-empty slice.
+    /// Otherwise, at most the maximum length of a header is copied into an owned header.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use ppp::v1::{Addresses, Header};
+    /// use std::io::IoSlice;
+    ///
+    /// let slices = [IoSlice::new(b"PROXY UNKN"), IoSlice::new(b"OWN\r\nHello")];
+    /// let header = Header::parse_slices(&slices).unwrap();
+    ///
+    /// assert_eq!(header, Header::new("PROXY UNKNOWN\r\n", Addresses::Unknown));
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn parse_slices<S: core::ops::Deref<Target = [u8]>>(
+        slices: &'a [S],
+    ) -> Result<Self, BinaryParseError> {
+        Header::parse_slices_with(slices, &ParseOptions::new())
+    }
+
+    /// Parses a header from a list of slices with the given `ParseOptions`.
+    /// See `parse_slices` for details.
+    #[cfg(feature = "alloc")]
+    pub fn parse_slices_with<S: core::ops::Deref<Target = [u8]>>(
+        slices: &'a [S],
+        options: &ParseOptions,
+    ) -> Result<Self, BinaryParseError> {
+        let first = scatter::first(slices);
+        let header = Header::parse_with(first, options);
+
+        if header.is_complete() || first.len() == scatter::total_len(slices) {
+            return header;
+        }
+
+        let bytes = scatter::coalesce(slices, MAX_LENGTH);
+
+        Header::parse_with(bytes.as_slice(), options).map(|header| header.to_owned())
     }
 }
 
@@ -180,6 +263,7 @@ impl FromStr for Addresses {
     }
 }
 
+#[cfg(feature = "alloc")]
 impl FromStr for Header<'static> {
     type Err = ParseError;
 
@@ -188,11 +272,13 @@ impl FromStr for Header<'static> {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
+    use std::borrow::Cow;
 
     #[test]
+    #[allow(invalid_from_utf8)]
     fn bytes_invalid_utf8() {
         let text = b"Hello \xF0\x90\x80World\r\n";
 
@@ -241,6 +327,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_partial_destination_port() {
+        let text = "PROXY TCP4 255.255.255.255 255.255.255.255 65535 ";
+
+        assert_eq!(
+            Header::try_from(text).unwrap_err(),
+            ParseError::MissingDestinationPort
+        );
+        assert_eq!(
+            Header::try_from(text.as_bytes()).unwrap_err(),
+            ParseError::MissingDestinationPort.into()
+        );
+    }
+
     #[test]
     fn parse_tcp4_invalid() {
         let text = "PROXY TCP4 255.255.255.255 256.255.255.255 65535 65535\r\n";
@@ -531,7 +631,9 @@ mod tests {
 
         assert_eq!(
             Header::try_from(text),
-            Err(ParseError::InvalidSourceAddress("".parse::<Ipv4Addr>().unwrap_err()).into())
+            Err(ParseError::InvalidSourceAddress(
+                "".parse::<Ipv4Addr>().unwrap_err()
+            ))
         );
         assert_eq!(
             Header::try_from(text.as_bytes()),
@@ -722,4 +824,73 @@ mod tests {
             ParseError::InvalidPrefix.into()
         );
     }
+
+    #[test]
+    fn parse_slices() {
+        let text = b"PROXY TCP6 ::1 ::2 80 443\r\nHello";
+        let expected = Header::try_from(text.as_slice()).unwrap();
+
+        for split in 0..text.len() {
+            let (left, right) = text.split_at(split);
+            let slices = [b"".as_slice(), left, right];
+            let header = Header::parse_slices(&slices).unwrap();
+
+            assert_eq!(header, expected);
+            assert_eq!(
+                matches!(header.header, Cow::Borrowed(_)),
+                split == 0 || split >= expected.header.len()
+            );
+        }
+    }
+
+    #[test]
+    fn parse_slices_errors() {
+        assert_eq!(
+            Header::parse_slices(&[b"PROXY TCP4 ".as_slice(), b"127.0.0.1"]),
+            Err(ParseError::MissingDestinationAddress.into())
+        );
+        assert_eq!(
+            Header::parse_slices(&[b"PROXY ".as_slice(), b"TCP5"]),
+            Err(ParseError::InvalidProtocol.into())
+        );
+        assert_eq!(
+            Header::parse_slices::<&[u8]>(&[]),
+            Err(ParseError::MissingPrefix.into())
+        );
+    }
+
+    #[test]
+    fn parse_with_disabled() {
+        let options = ParseOptions::new().v1(false);
+
+        assert_eq!(
+            Header::parse_with(b"PROXY UNKNOWN\r\n", &options),
+            Err(ParseError::Disabled.into())
+        );
+        assert_eq!(
+            Header::parse_str_with("PROXY UNKNOWN\r\n", &options),
+            Err(ParseError::Disabled)
+        );
+    }
+
+    #[test]
+    fn parse_with_lenient_ports() {
+        let text = "PROXY TCP4 127.0.0.1 192.168.1.1 080 0443\r\n";
+        let lenient = ParseOptions::new().allow_v1_leading_zeros(true);
+        let expected = Header::new(
+            text,
+            Addresses::new_tcp4([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        );
+
+        assert_eq!(
+            Header::try_from(text),
+            Err(ParseError::InvalidSourcePort(None))
+        );
+        assert_eq!(Header::parse_str_with(text, &lenient), Ok(expected.clone()));
+        assert_eq!(Header::parse_with(text.as_bytes(), &lenient), Ok(expected));
+        assert!(matches!(
+            Header::parse_str_with("PROXY TCP4 127.0.0.1 192.168.1.1 080 99999\r\n", &lenient),
+            Err(ParseError::InvalidDestinationPort(Some(_)))
+        ));
+    }
 }
@@ -1,9 +1,11 @@
 //! The data model to represent the test PROXY protocol header.
 
 use crate::ip::{IPv4, IPv6};
-use std::borrow::Cow;
-use std::fmt;
-use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use crate::MaybeOwned;
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, string::ToString};
+use core::fmt;
+use core::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
 
 /// The prefix of the PROXY protocol header.
 pub const PROTOCOL_PREFIX: &str = "PROXY";
@@ -95,7 +97,7 @@ pub const SEPARATOR: char = ' ';
 /// ```
 #[derive(Clone, Debug, PartialEq)]
 pub struct Header<'a> {
-    pub header: Cow<'a, str>,
+    pub header: MaybeOwned<'a, str>,
     pub addresses: Addresses,
 }
 
@@ -103,12 +105,13 @@ impl<'a> Header<'a> {
     /// Creates a new `Header` with the given addresses and a reference to the original input.
     pub fn new<H: Into<&'a str>, A: Into<Addresses>>(header: H, addresses: A) -> Self {
         Header {
-            header: Cow::Borrowed(header.into()),
+            header: crate::borrowed(header.into()),
             addresses: addresses.into(),
         }
     }
 
     /// Creates an owned clone of this [`Header`].
+    #[cfg(feature = "alloc")]
     pub fn to_owned(&self) -> Header<'static> {
         Header {
             header: Cow::Owned::<'static>(self.header.to_string()),
@@ -196,8 +199,10 @@ impl<'a> Header<'a> {
 ///
 /// assert_eq!(Err(ParseError::InvalidProtocol), "PROXY tcp4\r\n".parse::<Addresses>());
 /// ```
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Addresses {
+    #[default]
     Unknown,
     Tcp4(IPv4),
     Tcp6(IPv6),
@@ -244,12 +249,6 @@ impl Addresses {
     }
 }
 
-impl Default for Addresses {
-    fn default() -> Self {
-        Addresses::Unknown
-    }
-}
-
 impl From<(SocketAddr, SocketAddr)> for Addresses {
     fn from(addresses: (SocketAddr, SocketAddr)) -> Self {
         match addresses {
@@ -290,18 +289,6 @@ impl<'a> fmt::Display for Header<'a> {
 
 impl fmt::Display for Addresses {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::Unknown => f.write_str("PROXY UNKNOWN\r\n"),
-            Self::Tcp4(a) => write!(
-                f,
-                "PROXY TCP4 {} {} {} {}\r\n",
-                a.source_address, a.destination_address, a.source_port, a.destination_port
-            ),
-            Self::Tcp6(a) => write!(
-                f,
-                "PROXY TCP6 {} {} {} {}\r\n",
-                a.source_address, a.destination_address, a.source_port, a.destination_port
-            ),
-        }
+        self.with_str(|header| f.write_str(header))
     }
 }
@@ -0,0 +1,509 @@
+//! Integration with the `Buf` and `BufMut` traits of the `bytes` crate.
+
+use crate::v2::{
+    Addresses, BuildError, Builder, Command, Header, ParseError, Protocol, TypeLengthValues,
+    Version, MINIMUM_LENGTH,
+};
+use crate::{HeaderInfo, ParseOptions, Transport};
+use bytes::{Buf, BufMut, Bytes};
+use core::fmt;
+use core::net::SocketAddr;
+use std::io::IoSlice;
+
+/// A PROXY protocol v2 header that owns its underlying bytes.
+/// Cloning an `OwnedHeader` only increments the reference count of the shared `Bytes`.
+/// The header is accessed through its borrowed `Header` view (i.e. `as_header`).
+///
+/// ## Examples
+/// ```rust
+/// use bytes::{Buf, Bytes};
+/// use ppp::v2::{Builder, Command, Header, IPv4, Protocol, Version};
+///
+/// let header = Builder::with_addresses(
+///     Version::Two | Command::Proxy,
+///     Protocol::Stream,
+///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+/// )
+/// .build()
+/// .unwrap();
+///
+/// let mut buffer = Bytes::from(header.clone()).chain(&b"Hello, World!"[..]);
+/// let owned = Header::parse_buf(&mut buffer).unwrap();
+///
+/// assert_eq!(owned.as_bytes(), header.as_slice());
+/// assert_eq!(owned.as_header(), Header::try_from(header.as_slice()).unwrap());
+/// assert_eq!(owned.as_header().tlvs().count(), 0);
+/// assert_eq!(buffer.chunk(), b"Hello, World!");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct OwnedHeader {
+    pub header: Bytes,
+    pub version: Version,
+    pub command: Command,
+    pub protocol: Protocol,
+    pub addresses: Addresses,
+}
+
+impl<'a> Header<'a> {
+    /// Parses a header from the start of the buffer, which may be split across multiple chunks (i.e. `Chain` or `VecDeque`).
+    /// On success, the header's bytes are consumed from the buffer and any remaining bytes are left in place.
+    /// On error, the buffer is left unchanged.
+    /// The exceptions are a TLV validation error (i.e. `ParseOptions::validate_tlvs`) in a buffer split across more than 16 chunks,
+    /// and any error in a buffer whose first 16 bytes cannot be peeked at (i.e. a `Buf` that does not implement `chunks_vectored`),
+    /// which are consumed along with as much of the header as is available.
+    ///
+    /// When the header is contiguous in a `Bytes` buffer, the returned header shares the buffer's memory.
+    pub fn parse_buf<B: Buf + ?Sized>(buf: &mut B) -> Result<OwnedHeader, ParseError> {
+        Header::parse_buf_with(buf, &ParseOptions::new())
+    }
+
+    /// Parses a header from the start of the buffer with the given `ParseOptions`.
+    /// See `parse_buf` for details.
+    pub fn parse_buf_with<B: Buf + ?Sized>(
+        buf: &mut B,
+        options: &ParseOptions,
+    ) -> Result<OwnedHeader, ParseError> {
+        let mut fixed = [0; MINIMUM_LENGTH];
+        let peeked = peek(buf, &mut fixed);
+
+        if peeked < MINIMUM_LENGTH && buf.remaining() >= MINIMUM_LENGTH {
+            return parse_consumed(buf, options);
+        }
+
+        let length = match Header::parse_with(&fixed[..peeked], options) {
+            Ok(header) => header.len(),
+            Err(ParseError::Partial(_, length)) => MINIMUM_LENGTH + length,
+            Err(error) => return Err(error),
+        };
+
+        if buf.remaining() < length {
+            return Err(ParseError::Partial(
+                buf.remaining() - MINIMUM_LENGTH,
+                length - MINIMUM_LENGTH,
+            ));
+        }
+
+        if !options.walk_tlvs() {
+            // Every other option is checked on the fixed portion of the header, so only the bytes remain to be parsed.
+            return OwnedHeader::try_from(buf.copy_to_bytes(length));
+        }
+
+        let mut header = vec![0; length];
+
+        if peek(buf, &mut header) == length {
+            Header::parse_with(&header, options)?;
+            OwnedHeader::try_from(buf.copy_to_bytes(length))
+        } else {
+            // The buffer has too many chunks to peek at the whole header, so the TLVs are validated after it is consumed.
+            let bytes = buf.copy_to_bytes(length);
+
+            Header::parse_with(&bytes, options)?;
+            OwnedHeader::try_from(bytes)
+        }
+    }
+}
+
+/// Parses a header by consuming it from a buffer whose chunks cannot be peeked at.
+/// Only the available bytes of the header are consumed, so the buffer keeps any bytes after it.
+fn parse_consumed<B: Buf + ?Sized>(
+    buf: &mut B,
+    options: &ParseOptions,
+) -> Result<OwnedHeader, ParseError> {
+    let fixed = buf.copy_to_bytes(MINIMUM_LENGTH);
+    let length = match Header::parse_with(&fixed, options) {
+        Ok(_) => return OwnedHeader::try_from(fixed),
+        Err(ParseError::Partial(_, length)) => length,
+        Err(error) => return Err(error),
+    };
+
+    if buf.remaining() < length {
+        let remaining = buf.remaining();
+
+        buf.advance(remaining);
+
+        return Err(ParseError::Partial(remaining, length));
+    }
+
+    let mut header = Vec::with_capacity(MINIMUM_LENGTH + length);
+
+    header.extend_from_slice(&fixed);
+    header.resize(MINIMUM_LENGTH + length, 0);
+    buf.copy_to_slice(&mut header[MINIMUM_LENGTH..]);
+
+    Header::parse_with(&header, options)?;
+    OwnedHeader::try_from(Bytes::from(header))
+}
+
+/// The maximum number of chunks of a buffer that are peeked at.
+const PEEK_CHUNKS: usize = 16;
+
+/// Copies the start of the buffer to the destination without advancing the buffer.
+/// Only the first `PEEK_CHUNKS` chunks of the buffer are copied.
+/// Returns the number of bytes copied.
+fn peek<B: Buf + ?Sized>(buf: &B, destination: &mut [u8]) -> usize {
+    let mut chunks = [IoSlice::new(&[]); PEEK_CHUNKS];
+    let count = buf.chunks_vectored(&mut chunks);
+    let mut copied = 0;
+
+    for chunk in &chunks[..count] {
+        let length = chunk.len().min(destination.len() - copied);
+
+        destination[copied..copied + length].copy_from_slice(&chunk[..length]);
+        copied += length;
+    }
+
+    copied
+}
+
+impl Builder {
+    /// Builds the header and writes it to the given buffer.
+    /// Returns the number of bytes written.
+    /// An error is returned if the header is invalid or the buffer does not have enough remaining capacity.
+    pub fn build_into<B: BufMut + ?Sized>(self, buf: &mut B) -> Result<usize, BuildError> {
+        let header = self.build()?;
+
+        if buf.remaining_mut() < header.len() {
+            return Err(BuildError::Capacity {
+                required: header.len(),
+                available: buf.remaining_mut(),
+            });
+        }
+
+        buf.put_slice(header.as_slice());
+
+        Ok(header.len())
+    }
+}
+
+impl OwnedHeader {
+    /// A borrowed `Header` view of this `OwnedHeader`.
+    pub fn as_header(&self) -> Header<'_> {
+        Header {
+            header: self.header.as_ref().into(),
+            version: self.version,
+            command: self.command,
+            protocol: self.protocol,
+            addresses: self.addresses,
+        }
+    }
+
+    /// The total length of this `OwnedHeader` in bytes.
+    pub fn len(&self) -> usize {
+        self.header.len()
+    }
+
+    /// Tests whether this `OwnedHeader`'s underlying bytes are empty.
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+    }
+
+    /// The underlying bytes of this `OwnedHeader`.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.header.as_ref()
+    }
+}
+
+impl TryFrom<Bytes> for OwnedHeader {
+    type Error = ParseError;
+
+    /// Parses a header from the start of the bytes without copying them.
+    /// Any bytes after the header are ignored.
+    fn try_from(bytes: Bytes) -> Result<Self, Self::Error> {
+        let header = Header::try_from(bytes.as_ref())?;
+
+        Ok(OwnedHeader {
+            header: bytes.slice(..header.len()),
+            version: header.version,
+            command: header.command,
+            protocol: header.protocol,
+            addresses: header.addresses,
+        })
+    }
+}
+
+impl<'a> From<Header<'a>> for OwnedHeader {
+    /// Converts the header without copying its bytes if it already owns them.
+    fn from(header: Header<'a>) -> Self {
+        OwnedHeader {
+            header: Bytes::from(header.header.into_owned()),
+            version: header.version,
+            command: header.command,
+            protocol: header.protocol,
+            addresses: header.addresses,
+        }
+    }
+}
+
+impl<'a> From<&Header<'a>> for OwnedHeader {
+    fn from(header: &Header<'a>) -> Self {
+        OwnedHeader {
+            header: Bytes::copy_from_slice(header.as_bytes()),
+            version: header.version,
+            command: header.command,
+            protocol: header.protocol,
+            addresses: header.addresses,
+        }
+    }
+}
+
+impl fmt::Display for OwnedHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_header().fmt(f)
+    }
+}
+
+impl HeaderInfo for OwnedHeader {
+    fn source(&self) -> Option<SocketAddr> {
+        self.as_header().source()
+    }
+
+    fn destination(&self) -> Option<SocketAddr> {
+        self.as_header().destination()
+    }
+
+    fn is_local(&self) -> bool {
+        self.command == Command::Local
+    }
+
+    fn transport(&self) -> Transport {
+        self.protocol.into()
+    }
+
+    fn tlvs(&self) -> TypeLengthValues<'_> {
+        TypeLengthValues::from(&self.header[self.as_header().address_bytes_end()..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::{IPv6, Type, PROTOCOL_PREFIX};
+    use bytes::BytesMut;
+    use std::collections::VecDeque;
+
+    fn header() -> Vec<u8> {
+        Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv6::new([1; 8], [2; 8], 80, 443),
+        )
+        .write_tlv(Type::Authority, b"example.com")
+        .unwrap()
+        .build()
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_bytes_shares_memory() {
+        let expected = header();
+        let mut input = BytesMut::from(expected.as_slice());
+        input.extend_from_slice(b"Hello");
+        let mut input = input.freeze();
+        let start = input.as_ptr();
+
+        let header = Header::parse_buf(&mut input).unwrap();
+
+        assert_eq!(header.as_bytes(), expected.as_slice());
+        assert_eq!(header.header.as_ptr(), start);
+        assert_eq!(header.clone().header.as_ptr(), start);
+        assert_eq!(input.as_ref(), b"Hello");
+        assert_eq!(header.as_header().tlvs().count(), 1);
+    }
+
+    #[test]
+    fn parse_chain_split_in_fixed_header() {
+        let expected = header();
+
+        for split in 1..expected.len() {
+            let (left, right) = expected.split_at(split);
+            let mut input = left.chain(right);
+
+            let header = Header::parse_buf(&mut input).unwrap();
+
+            assert_eq!(header.as_bytes(), expected.as_slice());
+            assert!(!input.has_remaining());
+        }
+    }
+
+    #[test]
+    fn parse_wrapped_vec_deque() {
+        let expected = header();
+        let mut input = VecDeque::from(expected[5..].to_vec());
+
+        input.push_back(b'!');
+
+        for byte in expected[..5].iter().rev() {
+            input.push_front(*byte);
+        }
+
+        assert!(input.chunk().len() < expected.len());
+
+        let header = Header::parse_buf(&mut input).unwrap();
+
+        assert_eq!(header.as_bytes(), expected.as_slice());
+        assert_eq!(input, [b'!']);
+    }
+
+    #[test]
+    fn parse_incomplete_leaves_buffer() {
+        let expected = header();
+        let mut input = &expected[..10];
+
+        assert_eq!(
+            Header::parse_buf(&mut input),
+            Err(ParseError::Incomplete(10))
+        );
+        assert_eq!(input.len(), 10);
+
+        let mut input = &expected[..20];
+
+        assert_eq!(
+            Header::parse_buf(&mut input),
+            Err(ParseError::Partial(4, expected.len() - MINIMUM_LENGTH))
+        );
+        assert_eq!(input.len(), 20);
+    }
+
+    #[test]
+    fn parse_invalid_leaves_buffer() {
+        let mut input = Vec::from(PROTOCOL_PREFIX);
+        input.extend([0x21, 0x50, 0, 0]);
+        let mut input = input.as_slice();
+
+        assert_eq!(
+            Header::parse_buf(&mut input),
+            Err(ParseError::AddressFamily(0x50))
+        );
+        assert_eq!(input.len(), MINIMUM_LENGTH);
+    }
+
+    #[test]
+    fn build_into() {
+        let expected = header();
+        let builder = || {
+            Builder::with_addresses(
+                Version::Two | Command::Proxy,
+                Protocol::Stream,
+                IPv6::new([1; 8], [2; 8], 80, 443),
+            )
+            .write_tlv(Type::Authority, b"example.com")
+            .unwrap()
+        };
+        let mut output = BytesMut::from(&b"prefix"[..]);
+
+        assert_eq!(builder().build_into(&mut output).unwrap(), expected.len());
+        assert_eq!(&output[..6], b"prefix");
+        assert_eq!(&output[6..], expected.as_slice());
+
+        let mut small = [0; 16];
+        let error = builder().build_into(&mut small.as_mut_slice()).unwrap_err();
+
+        assert_eq!(
+            error,
+            BuildError::Capacity {
+                required: expected.len(),
+                available: 16
+            }
+        );
+    }
+
+    #[test]
+    fn owned_from_header() {
+        let bytes = header();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+        let owned = OwnedHeader::from(&header);
+
+        assert_eq!(owned.as_header(), header);
+        assert_eq!(owned.to_string(), header.to_string());
+        assert_eq!(HeaderInfo::source(&owned), HeaderInfo::source(&header));
+        assert!(HeaderInfo::tlvs(&owned).eq(header.tlvs()));
+
+        let owned_header = header.to_owned();
+        let start = owned_header.as_bytes().as_ptr();
+        let owned = OwnedHeader::from(owned_header);
+
+        assert_eq!(owned.header.as_ptr(), start);
+        assert_eq!(owned.as_header(), header);
+    }
+
+    /// A buffer of two chunks that only exposes its first chunk, like the default `chunks_vectored`.
+    struct Split {
+        first: Vec<u8>,
+        second: Vec<u8>,
+    }
+
+    impl Buf for Split {
+        fn remaining(&self) -> usize {
+            self.first.len() + self.second.len()
+        }
+
+        fn chunk(&self) -> &[u8] {
+            if self.first.is_empty() {
+                &self.second
+            } else {
+                &self.first
+            }
+        }
+
+        fn advance(&mut self, count: usize) {
+            let first = count.min(self.first.len());
+
+            self.first.drain(..first);
+            self.second.drain(..count - first);
+        }
+    }
+
+    #[test]
+    fn parse_non_contiguous_buf() {
+        let expected = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            crate::v2::IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .build()
+        .unwrap();
+        let split = |at: usize, extra: &[u8]| Split {
+            first: expected[..at].to_vec(),
+            second: [&expected[at..], extra].concat(),
+        };
+
+        assert_eq!(expected.len(), 28);
+
+        let mut input = split(8, b"!");
+        let options = ParseOptions::new().validate_tlvs(true);
+
+        assert_eq!(
+            Header::parse_buf_with(&mut input, &options)
+                .unwrap()
+                .as_bytes(),
+            expected.as_slice()
+        );
+        assert_eq!(input.chunk(), b"!");
+
+        let mut input = split(20, b"");
+
+        assert_eq!(
+            Header::parse_buf(&mut input).unwrap().as_bytes(),
+            expected.as_slice()
+        );
+        assert!(!input.has_remaining());
+    }
+
+    #[test]
+    fn parse_validated_tlvs_leaves_buffer() {
+        let mut expected = header();
+        let length = expected.len();
+        expected[length - 12] = 0xFF;
+        let options = ParseOptions::new().validate_tlvs(true);
+
+        let (left, right) = expected.split_at(20);
+        let mut input = left.chain(right);
+
+        assert!(matches!(
+            Header::parse_buf_with(&mut input, &options),
+            Err(ParseError::MalformedTLV { .. })
+        ));
+        assert_eq!(input.remaining(), length);
+        assert!(Header::parse_buf(&mut input).is_ok());
+    }
+}
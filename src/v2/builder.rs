@@ -1,9 +1,11 @@
 //! Builder pattern to generate both valid and invalid PROXY protocol v2 headers.
 
+use crate::v2::canonical;
 use crate::v2::{
-    Addresses, Protocol, Type, TypeLengthValue, TypeLengthValues, LENGTH, MINIMUM_LENGTH,
-    MINIMUM_TLV_LENGTH, PROTOCOL_PREFIX,
+    validate_tlvs, Addresses, BuildError, Command, Header, Protocol, Type, TypeLengthValue,
+    TypeLengthValues, Version, LENGTH, MINIMUM_LENGTH, MINIMUM_TLV_LENGTH, PROTOCOL_PREFIX,
 };
+use crate::ParseOptions;
 use std::io::{self, Write};
 
 /// `Write` interface for the builder's internal buffer.
@@ -28,6 +30,7 @@ pub struct Writer {
 
 /// Implementation of the builder pattern for PROXY protocol v2 headers.
 /// Supports both valid and invalid headers via the `write_payload` and `write_payloads` functions.
+/// Use `Builder::checked` when only valid headers should be built.
 ///
 /// ## Examples
 /// ```rust
@@ -58,6 +61,32 @@ pub struct Builder {
     addresses: Addresses,
     length: Option<u16>,
     additional_capacity: usize,
+    canonical: bool,
+}
+
+/// Implementation of the builder pattern for valid PROXY protocol v2 headers only.
+/// Created by `Builder::checked`.
+/// Unlike `Builder`, neither the length nor raw payloads can be set,
+/// and every `TypeLengthValue` is validated as it is written, so `build` does not parse the header again.
+///
+/// ## Examples
+/// ```rust
+/// use ppp::v2::{BuildError, Builder, Command, IPv4, Protocol, Type};
+///
+/// let builder = Builder::checked(
+///     Command::Proxy,
+///     Protocol::Stream,
+///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+/// );
+///
+/// assert!(matches!(
+///     builder.write_tlv(Type::SSL, &[0]),
+///     Err(BuildError::Invalid(_))
+/// ));
+/// ```
+#[derive(Debug)]
+pub struct CheckedBuilder {
+    builder: Builder,
 }
 
 impl Writer {
@@ -77,7 +106,7 @@ impl From<Vec<u8>> for Writer {
 impl Write for Writer {
     fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
         if self.bytes.len() > (u16::MAX as usize) + MINIMUM_LENGTH {
-            Err(io::ErrorKind::WriteZero.into())
+            Err(BuildError::PayloadTooLong(self.bytes.len() + buffer.len() - MINIMUM_LENGTH).into())
         } else {
             self.bytes.extend_from_slice(buffer);
             Ok(buffer.len())
@@ -93,6 +122,7 @@ impl Write for Writer {
 pub trait WriteToHeader {
     /// Write this instance to the given `Writer`.
     /// The `Writer` returns an IO error when an individual byte slice is longer than `u16::MAX`.
+    /// Errors caused by the length of the payload wrap a `BuildError`.
     /// However, the total length of the buffer may exceed `u16::MAX`.
     fn write_to(&self, writer: &mut Writer) -> io::Result<usize>;
 
@@ -135,7 +165,11 @@ impl WriteToHeader for Addresses {
 impl<'a> WriteToHeader for TypeLengthValue<'a> {
     fn write_to(&self, writer: &mut Writer) -> io::Result<usize> {
         if self.value.len() > u16::MAX as usize {
-            return Err(io::ErrorKind::WriteZero.into());
+            return Err(BuildError::TlvTooLong {
+                kind: self.kind,
+                len: self.value.len(),
+            }
+            .into());
         }
 
         writer.write_all([self.kind].as_slice())?;
@@ -146,13 +180,17 @@ impl<'a> WriteToHeader for TypeLengthValue<'a> {
     }
 }
 
-impl<'a, T: Copy + Into<u8>> WriteToHeader for (T, &'a [u8]) {
+impl<T: Copy + Into<u8>> WriteToHeader for (T, &[u8]) {
     fn write_to(&self, writer: &mut Writer) -> io::Result<usize> {
         let kind = self.0.into();
         let value = self.1;
 
         if value.len() > u16::MAX as usize {
-            return Err(io::ErrorKind::WriteZero.into());
+            return Err(BuildError::TlvTooLong {
+                kind,
+                len: value.len(),
+            }
+            .into());
         }
 
         writer.write_all([kind].as_slice())?;
@@ -178,7 +216,7 @@ impl WriteToHeader for [u8] {
         let slice = self;
 
         if slice.len() > u16::MAX as usize {
-            return Err(io::ErrorKind::WriteZero.into());
+            return Err(BuildError::PayloadTooLong(slice.len()).into());
         }
 
         writer.write_all(slice)?;
@@ -239,6 +277,7 @@ impl Builder {
             addresses: Addresses::Unspecified,
             length: None,
             additional_capacity: 0,
+            canonical: false,
         }
     }
 
@@ -259,6 +298,35 @@ impl Builder {
             addresses,
             length: None,
             additional_capacity: 0,
+            canonical: false,
+        }
+    }
+
+    /// Creates an instance of a `CheckedBuilder` for a valid version 2 header with the given `Command`, `Protocol` and `Addresses`.
+    /// The address family is determined from the variant of the `Addresses` given, so the two cannot mismatch.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use ppp::v2::{Builder, Command, Header, IPv4, Protocol, Type};
+    ///
+    /// let addresses = IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443);
+    /// let header = Builder::checked(Command::Proxy, Protocol::Stream, addresses)
+    ///     .write_tlv(Type::Authority, b"example.com")
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// let parsed = Header::try_from(header.as_slice()).unwrap();
+    ///
+    /// assert_eq!(parsed.addresses, addresses.into());
+    /// assert_eq!(parsed.len(), header.len());
+    /// ```
+    pub fn checked<T: Into<Addresses>>(
+        command: Command,
+        protocol: Protocol,
+        addresses: T,
+    ) -> CheckedBuilder {
+        CheckedBuilder {
+            builder: Builder::with_addresses(Version::Two | command, protocol, addresses),
         }
     }
 
@@ -281,9 +349,43 @@ impl Builder {
         self
     }
 
+    /// Sets whether the `TypeLengthValue`s are reordered into canonical order on `build`:
+    /// sorted by type code, keeping duplicates in the order they were written.
+    /// Headers built from the same `TypeLengthValue`s are then equal byte for byte, regardless of the order of the writes.
+    /// `build` returns a `BuildError::Invalid` if the `TypeLengthValue`s cannot be parsed.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use ppp::v2::{Builder, Command, IPv4, Protocol, Type, Version};
+    ///
+    /// let builder = || Builder::with_addresses(
+    ///     Version::Two | Command::Proxy,
+    ///     Protocol::Stream,
+    ///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+    /// )
+    /// .canonical_order(true);
+    ///
+    /// assert_eq!(
+    ///     builder()
+    ///         .write_tlv(Type::UniqueId, b"abc").unwrap()
+    ///         .write_tlv(Type::Authority, b"example.com").unwrap()
+    ///         .build()
+    ///         .unwrap(),
+    ///     builder()
+    ///         .write_tlv(Type::Authority, b"example.com").unwrap()
+    ///         .write_tlv(Type::UniqueId, b"abc").unwrap()
+    ///         .build()
+    ///         .unwrap()
+    /// );
+    /// ```
+    pub fn canonical_order(mut self, canonical: bool) -> Self {
+        self.canonical = canonical;
+        self
+    }
+
     /// Writes a iterable set of payloads in order to the buffer.
     /// No bytes are added by this `Builder` as a delimiter.
-    pub fn write_payloads<T, I, II>(mut self, payloads: II) -> io::Result<Self>
+    pub fn write_payloads<T, I, II>(mut self, payloads: II) -> Result<Self, BuildError>
     where
         T: WriteToHeader,
         I: Iterator<Item = T>,
@@ -304,7 +406,7 @@ impl Builder {
 
     /// Writes a single payload to the buffer.
     /// No surrounding bytes (terminal or otherwise) are added by this `Builder`.
-    pub fn write_payload<T: WriteToHeader>(mut self, payload: T) -> io::Result<Self> {
+    pub fn write_payload<T: WriteToHeader>(mut self, payload: T) -> Result<Self, BuildError> {
         self.write_header()?;
         self.write_internal(payload)?;
 
@@ -314,13 +416,85 @@ impl Builder {
     /// Writes a Type-Length-Value as a payload.
     /// No surrounding bytes (terminal or otherwise) are added by this `Builder`.
     /// The length is determined by the length of the slice.
-    /// An error is returned when the length of the slice exceeds `u16::MAX`.
-    pub fn write_tlv(self, kind: impl Into<u8>, value: &[u8]) -> io::Result<Self> {
+    /// A `BuildError::TlvTooLong` is returned when the length of the slice exceeds `u16::MAX`.
+    pub fn write_tlv(self, kind: impl Into<u8>, value: &[u8]) -> Result<Self, BuildError> {
         self.write_payload(TypeLengthValue::new(kind, value))
     }
 
+    /// Writes a `NoOp` TLV so the length of the header is a multiple of the alignment,
+    /// i.e. so the data following the header starts at an aligned offset.
+    /// Nothing is written if the header is already aligned.
+    /// Otherwise, the padding is increased by whole alignments until it fits the 3-byte overhead of the TLV.
+    /// An alignment of 0 never adds padding.
+    ///
+    /// ## Examples
+    /// ```rust
+    /// use ppp::v2::{Builder, Command, IPv4, Protocol, Type, Version};
+    ///
+    /// let header = Builder::with_addresses(
+    ///     Version::Two | Command::Proxy,
+    ///     Protocol::Stream,
+    ///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+    /// )
+    /// .write_tlv(Type::Authority, b"example.com")
+    /// .unwrap()
+    /// .pad_to(16)
+    /// .unwrap()
+    /// .build()
+    /// .unwrap();
+    ///
+    /// assert_eq!(header.len() % 16, 0);
+    /// ```
+    pub fn pad_to(mut self, alignment: usize) -> Result<Self, BuildError> {
+        self.write_header()?;
+
+        if alignment == 0 {
+            return Ok(self);
+        }
+
+        let length = self.header.as_ref().map_or(0, Vec::len);
+        let mut padding = (alignment - length % alignment) % alignment;
+
+        if padding == 0 {
+            return Ok(self);
+        }
+
+        while padding < MINIMUM_TLV_LENGTH {
+            padding += alignment;
+        }
+
+        self.pad_to_len(length + padding)
+    }
+
+    /// Writes a `NoOp` TLV so the header is exactly `total` bytes long.
+    /// Nothing is written if the header already has the given length.
+    /// A `BuildError::Padding` is returned if the header is longer than `total`,
+    /// or is shorter by less than the 3-byte overhead of the TLV.
+    pub fn pad_to_len(mut self, total: usize) -> Result<Self, BuildError> {
+        self.write_header()?;
+
+        let length = self.header.as_ref().map_or(0, Vec::len);
+
+        if total == length {
+            return Ok(self);
+        }
+
+        if total < length + MINIMUM_TLV_LENGTH {
+            return Err(BuildError::Padding {
+                length,
+                target: total,
+            });
+        }
+
+        let padding = vec![0; total - length - MINIMUM_TLV_LENGTH];
+
+        self.write_internal(TypeLengthValue::new(Type::NoOp, &padding))?;
+
+        Ok(self)
+    }
+
     /// Writes to the underlying buffer without first writing the header bytes.
-    fn write_internal<T: WriteToHeader>(&mut self, payload: T) -> io::Result<()> {
+    fn write_internal<T: WriteToHeader>(&mut self, payload: T) -> Result<(), BuildError> {
         let mut writer = Writer::from(self.header.take().unwrap_or_default());
 
         payload.write_to(&mut writer)?;
@@ -332,7 +506,7 @@ impl Builder {
 
     /// Writes the protocol prefix, version, command, address family, protocol, and optional addresses to the buffer.
     /// Does nothing if the buffer is not empty.
-    fn write_header(&mut self) -> io::Result<()> {
+    fn write_header(&mut self) -> Result<(), BuildError> {
         if self.header.is_some() {
             return Ok(());
         }
@@ -356,30 +530,96 @@ impl Builder {
     }
 
     /// Builds the header and returns the underlying buffer.
-    /// If no length was explicitly set, returns an error when the length of the payload portion exceeds `u16::MAX`.
-    pub fn build(mut self) -> io::Result<Vec<u8>> {
+    /// If no length was explicitly set, returns a `BuildError::PayloadTooLong` when the length of the payload portion exceeds `u16::MAX`.
+    /// With `canonical_order`, the `TypeLengthValue`s are reordered into canonical order.
+    pub fn build(mut self) -> Result<Vec<u8>, BuildError> {
         self.write_header()?;
 
         let mut header = self.header.take().unwrap_or_default();
 
-        if self.length.is_some() {
-            return Ok(header);
-        }
+        if self.length.is_none() {
+            let payload_length = header[MINIMUM_LENGTH..].len();
+            let length = u16::try_from(payload_length)
+                .map_err(|_| BuildError::PayloadTooLong(payload_length))?
+                .to_be_bytes();
 
-        if let Ok(payload_length) = u16::try_from(header[MINIMUM_LENGTH..].len()) {
-            let length = payload_length.to_be_bytes();
             header[LENGTH..LENGTH + length.len()].copy_from_slice(length.as_slice());
-            Ok(header)
-        } else {
-            Err(io::ErrorKind::WriteZero.into())
         }
+
+        if self.canonical {
+            canonical::reorder(&mut header).map_err(BuildError::Invalid)?;
+        }
+
+        Ok(header)
+    }
+}
+
+impl CheckedBuilder {
+    /// Reserves the requested additional capacity in the underlying buffer.
+    pub fn reserve_capacity(mut self, capacity: usize) -> Self {
+        self.builder = self.builder.reserve_capacity(capacity);
+        self
+    }
+
+    /// Sets whether the `TypeLengthValue`s are reordered into canonical order on `build`.
+    /// See `Builder::canonical_order`.
+    pub fn canonical_order(mut self, canonical: bool) -> Self {
+        self.builder = self.builder.canonical_order(canonical);
+        self
+    }
+
+    /// Writes a Type-Length-Value as a payload.
+    /// A `BuildError::TlvTooLong` is returned when the length of the slice exceeds `u16::MAX`,
+    /// and a `BuildError::Invalid` when the `TypeLengthValue` is malformed (e.g. an `SSL` value without its fixed fields).
+    pub fn write_tlv(mut self, kind: impl Into<u8>, value: &[u8]) -> Result<Self, BuildError> {
+        self.builder.write_header()?;
+
+        let start = self.builder.header.as_ref().map_or(0, Vec::len);
+
+        self.builder
+            .write_internal(TypeLengthValue::new(kind, value))?;
+
+        let header = self.builder.header.as_deref().unwrap_or_default();
+
+        validate_tlvs(header, start, &ParseOptions::new()).map_err(BuildError::Invalid)?;
+
+        Ok(self)
+    }
+
+    /// Writes a `NoOp` TLV so the length of the header is a multiple of the alignment.
+    /// See `Builder::pad_to`.
+    pub fn pad_to(mut self, alignment: usize) -> Result<Self, BuildError> {
+        self.builder = self.builder.pad_to(alignment)?;
+        Ok(self)
+    }
+
+    /// Writes a `NoOp` TLV so the header is exactly `total` bytes long.
+    /// See `Builder::pad_to_len`.
+    pub fn pad_to_len(mut self, total: usize) -> Result<Self, BuildError> {
+        self.builder = self.builder.pad_to_len(total)?;
+        Ok(self)
+    }
+
+    /// Builds the header and returns the underlying buffer.
+    /// Returns a `BuildError::PayloadTooLong` when the length of the payload portion exceeds `u16::MAX`.
+    /// With debug assertions enabled, the header is also parsed back to verify it is valid.
+    pub fn build(self) -> Result<Vec<u8>, BuildError> {
+        let header = self.builder.build()?;
+
+        debug_assert_eq!(
+            Header::parse_with(header.as_slice(), &ParseOptions::new().validate_tlvs(true))
+                .map(|parsed| parsed.len()),
+            Ok(header.len()),
+        );
+
+        Ok(header)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::v2::{AddressFamily, Command, IPv4, IPv6, Protocol, Type, Unix, Version};
+    use crate::v2::{AddressFamily, IPv4, IPv6, ParseError, Unix};
 
     #[test]
     fn build_length_too_small() {
@@ -408,7 +648,63 @@ mod tests {
         .write_payload(vec![0u8; (u16::MAX as usize) + 1].as_slice())
         .unwrap_err();
 
-        assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+        assert_eq!(error, BuildError::PayloadTooLong((u16::MAX as usize) + 1));
+        assert_eq!(io::Error::from(error).kind(), io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn build_tlv_too_long() {
+        let error = Builder::new(
+            Version::Two | Command::Proxy,
+            AddressFamily::Unspecified | Protocol::Stream,
+        )
+        .write_tlv(
+            Type::Authority,
+            vec![0u8; (u16::MAX as usize) + 1].as_slice(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            BuildError::TlvTooLong {
+                kind: Type::Authority.into(),
+                len: (u16::MAX as usize) + 1
+            }
+        );
+    }
+
+    #[test]
+    fn build_total_payload_too_long() {
+        let error = Builder::new(
+            Version::Two | Command::Proxy,
+            AddressFamily::Unspecified | Protocol::Stream,
+        )
+        .write_payload(vec![0u8; u16::MAX as usize].as_slice())
+        .unwrap()
+        .write_payload(1u8)
+        .unwrap()
+        .build()
+        .unwrap_err();
+
+        assert_eq!(error, BuildError::PayloadTooLong((u16::MAX as usize) + 1));
+    }
+
+    #[test]
+    fn build_error_io_round_trip() {
+        let error = BuildError::TlvTooLong { kind: 1, len: 2 };
+
+        assert_eq!(
+            BuildError::from(io::Error::from(error)),
+            BuildError::TlvTooLong { kind: 1, len: 2 }
+        );
+        assert_eq!(
+            BuildError::from(io::Error::from(io::ErrorKind::BrokenPipe)),
+            BuildError::Io(io::ErrorKind::BrokenPipe)
+        );
+        assert_eq!(
+            io::Error::from(BuildError::Io(io::ErrorKind::BrokenPipe)).kind(),
+            io::ErrorKind::BrokenPipe
+        );
     }
 
     #[test]
@@ -623,4 +919,175 @@ mod tests {
 
         assert_eq!(header, expected);
     }
+
+    #[test]
+    fn build_checked() {
+        let addresses = IPv6::new([1; 8], [2; 8], 80, 443);
+        let expected =
+            Builder::with_addresses(Version::Two | Command::Local, Protocol::Datagram, addresses)
+                .write_tlv(Type::NoOp, &[0; 3])
+                .unwrap()
+                .pad_to(8)
+                .unwrap()
+                .build()
+                .unwrap();
+
+        let header = Builder::checked(Command::Local, Protocol::Datagram, addresses)
+            .write_tlv(Type::NoOp, &[0; 3])
+            .unwrap()
+            .pad_to(8)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(header, expected);
+    }
+
+    #[test]
+    fn build_checked_malformed_tlv() {
+        let builder = || {
+            Builder::checked(
+                Command::Proxy,
+                Protocol::Stream,
+                IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+            )
+        };
+
+        assert_eq!(
+            builder().write_tlv(Type::SSL, &[0]).unwrap_err(),
+            BuildError::Invalid(ParseError::MalformedTLV {
+                offset: 28,
+                required: 8,
+                available: 4
+            })
+        );
+        assert_eq!(
+            builder()
+                .write_tlv(Type::SSL, &[0, 0, 0, 0, 0, Type::NoOp as u8, 0, 9])
+                .unwrap_err(),
+            BuildError::Invalid(ParseError::MalformedTLV {
+                offset: 36,
+                required: 12,
+                available: 3
+            })
+        );
+        assert!(builder()
+            .write_tlv(Type::SSL, &[0, 0, 0, 0, 0, Type::NoOp as u8, 0, 0])
+            .unwrap()
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn pad_to_alignment() {
+        let builder = || {
+            Builder::with_addresses(
+                Version::Two | Command::Proxy,
+                Protocol::Stream,
+                IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+            )
+        };
+
+        // The addresses end at 28 bytes, so 4 bytes of padding fit exactly.
+        let mut expected = Vec::from(PROTOCOL_PREFIX);
+        expected.extend([
+            0x21, 0x11, 0, 16, 127, 0, 0, 1, 192, 168, 1, 1, 0, 80, 1, 187,
+        ]);
+        expected.extend([0x04, 0, 1, 0]);
+
+        assert_eq!(builder().pad_to(8).unwrap().build().unwrap(), expected);
+
+        // A gap of 2 bytes cannot hold a TLV, so padding extends to the next alignment.
+        let header = builder()
+            .write_tlv(Type::NoOp, &[0; 7])
+            .unwrap()
+            .pad_to(8)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(header.len(), 48);
+        assert_eq!(&header[38..41], &[0x04, 0, 7]);
+        assert!(Header::try_from(header.as_slice()).is_ok());
+
+        assert_eq!(builder().pad_to(4).unwrap().build().unwrap().len(), 28);
+        assert_eq!(builder().pad_to(0).unwrap().build().unwrap().len(), 28);
+    }
+
+    #[test]
+    fn pad_to_len() {
+        let builder = || {
+            Builder::with_addresses(
+                Version::Two | Command::Proxy,
+                Protocol::Stream,
+                IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+            )
+        };
+        let header = builder().pad_to_len(100).unwrap().build().unwrap();
+        let parsed = Header::try_from(header.as_slice()).unwrap();
+
+        assert_eq!(header.len(), 100);
+        assert_eq!(parsed.len(), 100);
+        assert_eq!(parsed.tlvs().skip_noop(true).count(), 0);
+        assert_eq!(builder().pad_to_len(28).unwrap().build().unwrap().len(), 28);
+        assert_eq!(
+            builder().pad_to_len(30).unwrap_err(),
+            BuildError::Padding {
+                length: 28,
+                target: 30
+            }
+        );
+        assert_eq!(
+            builder().pad_to_len(20).unwrap_err(),
+            BuildError::Padding {
+                length: 28,
+                target: 20
+            }
+        );
+        assert_eq!(
+            builder().pad_to_len(28 + 3 + 65536).unwrap_err(),
+            BuildError::TlvTooLong {
+                kind: 0x04,
+                len: 65536
+            }
+        );
+    }
+
+    #[test]
+    fn build_canonical_order() {
+        let builder = || {
+            Builder::with_addresses(
+                Version::Two | Command::Proxy,
+                Protocol::Stream,
+                IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+            )
+            .canonical_order(true)
+        };
+
+        let mut expected = Vec::from(PROTOCOL_PREFIX);
+        expected.extend([
+            0x21, 0x11, 0, 22, 127, 0, 0, 1, 192, 168, 1, 1, 0, 80, 1, 187,
+        ]);
+        expected.extend([0x01, 0, 0, 0x04, 0, 1, 2, 0x04, 0, 0]);
+
+        let header = builder()
+            .write_tlv(Type::NoOp, &[2])
+            .unwrap()
+            .write_tlv(Type::ALPN, &[])
+            .unwrap()
+            .write_tlv(Type::NoOp, &[])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(header, expected);
+        assert_eq!(
+            builder()
+                .write_payload([0x05u8, 0, 9].as_slice())
+                .unwrap()
+                .build()
+                .unwrap_err(),
+            BuildError::Invalid(ParseError::InvalidTLV(0x05, 9))
+        );
+    }
 }
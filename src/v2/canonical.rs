@@ -0,0 +1,194 @@
+//! Canonical ordering of the `TypeLengthValue`s in a header, for deterministic output.
+
+use crate::v2::{crc32c, Header, ParseError, Type, MINIMUM_TLV_LENGTH};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// The type code and absolute byte range of every `TypeLengthValue` in the header.
+fn tlv_ranges(header: &Header<'_>) -> Result<Vec<(u8, Range<usize>)>, ParseError> {
+    let mut offset = header.len() - header.tlv_bytes().len();
+    let mut ranges = Vec::new();
+
+    for tlv in header.tlvs() {
+        let tlv = tlv?;
+        let end = offset + MINIMUM_TLV_LENGTH + tlv.len();
+
+        ranges.push((tlv.kind, offset..end));
+        offset = end;
+    }
+
+    Ok(ranges)
+}
+
+/// The offsets of the checksum fields of the 4-byte `CRC32C` TLVs.
+fn checksums(ranges: &[(u8, Range<usize>)]) -> Vec<usize> {
+    ranges
+        .iter()
+        .filter(|(kind, range)| {
+            *kind == Type::CRC32C as u8 && range.len() == MINIMUM_TLV_LENGTH + 4
+        })
+        .map(|(_, range)| range.start + MINIMUM_TLV_LENGTH)
+        .collect()
+}
+
+/// Reorders the `TypeLengthValue`s of the header in place, by type code and stable for duplicates.
+/// Returns the offsets of the checksum fields of the 4-byte `CRC32C` TLVs after the reordering.
+pub(crate) fn reorder(bytes: &mut [u8]) -> Result<Vec<usize>, ParseError> {
+    let mut ranges = tlv_ranges(&Header::try_from(&*bytes)?)?;
+    let Some(start) = ranges.first().map(|(_, range)| range.start) else {
+        return Ok(Vec::new());
+    };
+
+    ranges.sort_by_key(|(kind, _)| *kind);
+
+    let mut tlvs = Vec::with_capacity(bytes.len() - start);
+    let mut sorted = Vec::with_capacity(ranges.len());
+
+    for (kind, range) in ranges {
+        let offset = start + tlvs.len();
+
+        tlvs.extend_from_slice(&bytes[range.clone()]);
+        sorted.push((kind, offset..offset + range.len()));
+    }
+
+    bytes[start..start + tlvs.len()].copy_from_slice(&tlvs);
+
+    Ok(checksums(&sorted))
+}
+
+impl<'a> Header<'a> {
+    /// Creates an owned copy of this `Header` with its `TypeLengthValue`s in canonical order:
+    /// sorted by type code, keeping duplicates in their original order.
+    /// Headers with the same fields and `TypeLengthValue`s are then equal byte for byte.
+    /// Valid `CRC32C` checksums are recomputed for the new order, while invalid ones are kept as is.
+    /// An error is returned if the `TypeLengthValue`s cannot be parsed.
+    ///
+    /// ## Examples
+    #[cfg_attr(feature = "std", doc = "```rust")]
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
+    /// use ppp::v2::{Builder, Command, Header, IPv4, Protocol, Type, Version};
+    ///
+    /// let builder = || Builder::with_addresses(
+    ///     Version::Two | Command::Proxy,
+    ///     Protocol::Stream,
+    ///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+    /// );
+    /// let first = builder()
+    ///     .write_tlv(Type::UniqueId, b"abc")
+    ///     .unwrap()
+    ///     .write_tlv(Type::Authority, b"example.com")
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// let second = builder()
+    ///     .write_tlv(Type::Authority, b"example.com")
+    ///     .unwrap()
+    ///     .write_tlv(Type::UniqueId, b"abc")
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// let first = Header::try_from(first.as_slice()).unwrap();
+    /// let second = Header::try_from(second.as_slice()).unwrap();
+    ///
+    /// assert_ne!(first.as_bytes(), second.as_bytes());
+    /// assert_eq!(
+    ///     first.canonicalize().unwrap().as_bytes(),
+    ///     second.canonicalize().unwrap().as_bytes()
+    /// );
+    /// ```
+    pub fn canonicalize(&self) -> Result<Header<'static>, ParseError> {
+        let mut bytes = self.as_bytes().to_vec();
+        let valid = crc32c::verify(&bytes, &checksums(&tlv_ranges(self)?));
+        let checksums = reorder(&mut bytes)?;
+
+        if valid && !checksums.is_empty() {
+            crc32c::seal(&mut bytes, &checksums);
+        }
+
+        Ok(Header {
+            header: bytes.into(),
+            version: self.version,
+            command: self.command,
+            protocol: self.protocol,
+            addresses: self.addresses,
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::v2::{Builder, Command, HeaderMut, IPv4, Protocol, Version};
+
+    fn builder() -> Builder {
+        Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+    }
+
+    #[test]
+    fn stable_for_duplicates() {
+        let bytes = builder()
+            .write_tlv(Type::NoOp, &[2])
+            .unwrap()
+            .write_tlv(Type::ALPN, b"h2")
+            .unwrap()
+            .write_tlv(Type::NoOp, &[1])
+            .unwrap()
+            .build()
+            .unwrap();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+        let canonical = header.canonicalize().unwrap();
+
+        assert_eq!(canonical.len(), header.len());
+        assert_eq!(canonical.addresses, header.addresses);
+        assert_eq!(
+            canonical.tlvs().collect::<Result<Vec<_>, _>>().unwrap(),
+            vec![
+                (Type::ALPN, b"h2".as_slice()).into(),
+                (Type::NoOp, [2].as_slice()).into(),
+                (Type::NoOp, [1].as_slice()).into(),
+            ]
+        );
+        assert_eq!(canonical.canonicalize().unwrap(), canonical);
+    }
+
+    #[test]
+    fn checksums() {
+        let bytes = builder()
+            .write_tlv(Type::UniqueId, b"abc")
+            .unwrap()
+            .write_tlv(Type::CRC32C, &[0; 4])
+            .unwrap()
+            .build()
+            .unwrap();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+        let sealed = HeaderMut::try_from(&header).unwrap().build().unwrap();
+        let sealed = Header::try_from(sealed.as_slice()).unwrap();
+        let canonical = sealed.canonicalize().unwrap();
+        let offsets = super::checksums(&tlv_ranges(&canonical).unwrap());
+
+        assert_eq!(offsets, vec![31]);
+        assert!(crc32c::verify(canonical.as_bytes(), &offsets));
+
+        let unsealed = header.canonicalize().unwrap();
+
+        assert_eq!(&unsealed.as_bytes()[31..35], &[0; 4]);
+    }
+
+    #[test]
+    fn invalid() {
+        let bytes = builder()
+            .write_tlv(Type::NoOp, &[])
+            .unwrap()
+            .write_payload([0x05u8, 0, 9].as_slice())
+            .unwrap()
+            .build()
+            .unwrap();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(header.canonicalize(), Err(ParseError::InvalidTLV(0x05, 9)));
+    }
+}
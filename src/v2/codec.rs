@@ -0,0 +1,496 @@
+//! Typed encoding and decoding of application-specific `TypeLengthValue`s.
+
+use crate::v2::{BuildError, CodecError, Header, TypeLengthValue};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A type stored as the value of a `TypeLengthValue` with a fixed type code.
+/// Applications usually implement this for the custom range of type codes (`0xE0` to `0xEF`).
+///
+/// ## Examples
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use ppp::v2::{Builder, Command, Encoded, Header, IPv4, Protocol, TlvCodec, Version};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct TenantId(u32);
+///
+/// impl TlvCodec for TenantId {
+///     const KIND: u8 = 0xE0;
+///     type Error = std::array::TryFromSliceError;
+///
+///     fn decode(value: &[u8]) -> Result<Self, Self::Error> {
+///         Ok(TenantId(u32::from_be_bytes(value.try_into()?)))
+///     }
+///
+///     fn encode(&self) -> Vec<u8> {
+///         self.0.to_be_bytes().to_vec()
+///     }
+/// }
+///
+/// let bytes = Builder::with_addresses(
+///     Version::Two | Command::Proxy,
+///     Protocol::Stream,
+///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+/// )
+/// .write_payload(Encoded(TenantId(42)))
+/// .unwrap()
+/// .build()
+/// .unwrap();
+/// let header = Header::try_from(bytes.as_slice()).unwrap();
+///
+/// assert_eq!(header.decode_tlv::<TenantId>().unwrap().unwrap(), TenantId(42));
+/// ```
+pub trait TlvCodec: Sized {
+    /// The type code of the `TypeLengthValue`.
+    const KIND: u8;
+
+    /// The error returned when a value cannot be decoded.
+    type Error;
+
+    /// Decodes the value of a `TypeLengthValue` with the type code `KIND`.
+    fn decode(value: &[u8]) -> Result<Self, Self::Error>;
+
+    /// Encodes this instance as the value of a `TypeLengthValue`.
+    fn encode(&self) -> Vec<u8>;
+
+    /// Encodes this instance as a `TypeLengthValue`, which can be inserted into a `HeaderMut`.
+    /// Returns a `BuildError::TlvTooLong` if the encoded value is longer than `u16::MAX` bytes.
+    fn to_tlv(&self) -> Result<TypeLengthValue<'static>, BuildError> {
+        let value = self.encode();
+
+        if value.len() > u16::MAX as usize {
+            return Err(BuildError::TlvTooLong {
+                kind: Self::KIND,
+                len: value.len(),
+            });
+        }
+
+        Ok(TypeLengthValue {
+            kind: Self::KIND,
+            value: value.into(),
+        })
+    }
+}
+
+/// Writes a `TlvCodec` as a `TypeLengthValue` with its type code, i.e. `Builder::write_payload(Encoded(value))`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Encoded<C>(pub C);
+
+#[cfg(feature = "std")]
+impl<C: TlvCodec> crate::v2::WriteToHeader for Encoded<C> {
+    fn write_to(&self, writer: &mut crate::v2::Writer) -> std::io::Result<usize> {
+        TypeLengthValue::new(C::KIND, &self.0.encode()).write_to(writer)
+    }
+}
+
+/// A function that decodes the value of a `TypeLengthValue` into the registry's types.
+type Decoder<T, E> = fn(&[u8]) -> Result<T, E>;
+
+/// A function that encodes the registry's type as the value of a `TypeLengthValue`, if it holds the codec's type.
+type Encoder<T> = fn(&T) -> Option<Vec<u8>>;
+
+/// A set of `TlvCodec`s that decode `TypeLengthValue`s into a single application type, by type code.
+/// Useful when every known `TypeLengthValue` of a header should be decoded (i.e. for logging).
+/// Codecs registered with `register_encoder` also encode the application type back into `TypeLengthValue`s.
+///
+/// ## Examples
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use ppp::v2::{
+///     Builder, Command, Encoded, Header, IPv4, Protocol, TlvCodec, TlvRegistry, Type, Version,
+/// };
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Metadata {
+///     Tenant(u8),
+///     Trace(Vec<u8>),
+/// }
+///
+/// struct Tenant(u8);
+/// struct Trace(Vec<u8>);
+///
+/// impl TlvCodec for Tenant {
+///     const KIND: u8 = 0xE0;
+///     type Error = &'static str;
+///
+///     fn decode(value: &[u8]) -> Result<Self, Self::Error> {
+///         match value {
+///             [tenant] => Ok(Tenant(*tenant)),
+///             _ => Err("expected a single byte"),
+///         }
+///     }
+///
+///     fn encode(&self) -> Vec<u8> {
+///         vec![self.0]
+///     }
+/// }
+///
+/// impl TlvCodec for Trace {
+///     const KIND: u8 = 0xE1;
+///     type Error = &'static str;
+///
+///     fn decode(value: &[u8]) -> Result<Self, Self::Error> {
+///         Ok(Trace(value.to_vec()))
+///     }
+///
+///     fn encode(&self) -> Vec<u8> {
+///         self.0.clone()
+///     }
+/// }
+///
+/// impl From<Tenant> for Metadata {
+///     fn from(tenant: Tenant) -> Self {
+///         Metadata::Tenant(tenant.0)
+///     }
+/// }
+///
+/// impl From<Trace> for Metadata {
+///     fn from(trace: Trace) -> Self {
+///         Metadata::Trace(trace.0)
+///     }
+/// }
+///
+/// impl TryFrom<&Metadata> for Tenant {
+///     type Error = ();
+///
+///     fn try_from(metadata: &Metadata) -> Result<Self, Self::Error> {
+///         match metadata {
+///             Metadata::Tenant(tenant) => Ok(Tenant(*tenant)),
+///             _ => Err(()),
+///         }
+///     }
+/// }
+///
+/// let registry = TlvRegistry::<Metadata, &'static str>::new()
+///     .register::<Tenant>()
+///     .register::<Trace>()
+///     .register_encoder::<Tenant>();
+/// let bytes = Builder::with_addresses(
+///     Version::Two | Command::Proxy,
+///     Protocol::Stream,
+///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+/// )
+/// .write_payload(Encoded(Tenant(7)))
+/// .unwrap()
+/// .write_tlv(Type::Authority, b"example.com")
+/// .unwrap()
+/// .write_payload(Encoded(Trace(vec![1, 2])))
+/// .unwrap()
+/// .build()
+/// .unwrap();
+/// let header = Header::try_from(bytes.as_slice()).unwrap();
+///
+/// assert_eq!(
+///     registry.decode_all(&header).collect::<Result<Vec<_>, _>>().unwrap(),
+///     vec![Metadata::Tenant(7), Metadata::Trace(vec![1, 2])]
+/// );
+/// assert_eq!(
+///     registry.encode(&Metadata::Tenant(7)).unwrap().unwrap(),
+///     Tenant(7).to_tlv().unwrap()
+/// );
+/// assert!(registry.encode(&Metadata::Trace(vec![1, 2])).is_none());
+/// ```
+pub struct TlvRegistry<T, E> {
+    decoders: [Option<Decoder<T, E>>; 256],
+    encoders: Vec<(u8, Encoder<T>)>,
+}
+
+impl<T, E> TlvRegistry<T, E> {
+    /// Creates an empty `TlvRegistry`.
+    pub fn new() -> Self {
+        TlvRegistry {
+            decoders: [None; 256],
+            encoders: Vec::new(),
+        }
+    }
+
+    /// Registers the `TlvCodec` for its type code, replacing any codec previously registered for the same code.
+    pub fn register<C>(mut self) -> Self
+    where
+        C: TlvCodec + Into<T>,
+        C::Error: Into<E>,
+    {
+        self.decoders[C::KIND as usize] = Some(decode::<C, T, E>);
+        self
+    }
+
+    /// Registers the `TlvCodec` to encode the values of the registry's type that convert into it.
+    /// Encoders are tried in the order they were registered.
+    pub fn register_encoder<C>(mut self) -> Self
+    where
+        C: TlvCodec + for<'v> TryFrom<&'v T>,
+    {
+        self.encoders.push((C::KIND, encode::<C, T>));
+        self
+    }
+
+    /// Tests whether a `TlvCodec` is registered for the given type code.
+    pub fn is_registered<K: Into<u8>>(&self, kind: K) -> bool {
+        self.decoders[kind.into() as usize].is_some()
+    }
+
+    /// Decodes the `TypeLengthValue` with the codec registered for its type.
+    /// Returns `None` if no codec is registered for the type.
+    pub fn decode(&self, tlv: &TypeLengthValue<'_>) -> Option<Result<T, E>> {
+        self.decoders[tlv.kind as usize].map(|decoder| decoder(tlv.value.as_ref()))
+    }
+
+    /// Encodes the value as a `TypeLengthValue` with the first registered encoder that accepts it.
+    /// Returns `None` if no encoder accepts the value.
+    pub fn encode(&self, value: &T) -> Option<Result<TypeLengthValue<'static>, BuildError>> {
+        self.encoders.iter().find_map(|(kind, encoder)| {
+            encoder(value).map(|encoded| {
+                if encoded.len() > u16::MAX as usize {
+                    return Err(BuildError::TlvTooLong {
+                        kind: *kind,
+                        len: encoded.len(),
+                    });
+                }
+
+                Ok(TypeLengthValue {
+                    kind: *kind,
+                    value: encoded.into(),
+                })
+            })
+        })
+    }
+
+    /// An `Iterator` of the decoded `TypeLengthValue`s of the header that have a registered codec.
+    /// A malformed `TypeLengthValue` ends the iteration with an error.
+    pub fn decode_all<'a>(
+        &'a self,
+        header: &'a Header<'_>,
+    ) -> impl Iterator<Item = Result<T, CodecError<E>>> + 'a {
+        header.tlvs().filter_map(move |tlv| match tlv {
+            Ok(tlv) => self
+                .decode(&tlv)
+                .map(|result| result.map_err(CodecError::Decode)),
+            Err(error) => Some(Err(CodecError::Parse(error))),
+        })
+    }
+}
+
+/// Decodes a value with `C` and converts the result into the registry's types.
+fn decode<C, T, E>(value: &[u8]) -> Result<T, E>
+where
+    C: TlvCodec + Into<T>,
+    C::Error: Into<E>,
+{
+    C::decode(value).map(Into::into).map_err(Into::into)
+}
+
+/// Encodes a value with `C` if it converts into `C`.
+fn encode<C, T>(value: &T) -> Option<Vec<u8>>
+where
+    C: TlvCodec + for<'v> TryFrom<&'v T>,
+{
+    C::try_from(value).ok().map(|codec| codec.encode())
+}
+
+impl<T, E> Default for TlvRegistry<T, E> {
+    fn default() -> Self {
+        TlvRegistry::new()
+    }
+}
+
+impl<T, E> Clone for TlvRegistry<T, E> {
+    fn clone(&self) -> Self {
+        TlvRegistry {
+            decoders: self.decoders,
+            encoders: self.encoders.clone(),
+        }
+    }
+}
+
+impl<T, E> fmt::Debug for TlvRegistry<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kinds: Vec<u8> = (0..=u8::MAX)
+            .filter(|kind| self.is_registered(*kind))
+            .collect();
+
+        let encoders: Vec<u8> = self.encoders.iter().map(|(kind, _)| *kind).collect();
+
+        f.debug_struct("TlvRegistry")
+            .field("kinds", &kinds)
+            .field("encoders", &encoders)
+            .finish()
+    }
+}
+
+impl<'a> Header<'a> {
+    /// Decodes the first `TypeLengthValue` with the codec's type code.
+    /// Returns `None` if the header has no `TypeLengthValue` of that type.
+    pub fn decode_tlv<C: TlvCodec>(&self) -> Option<Result<C, CodecError<C::Error>>> {
+        self.tlv(C::KIND).map(|tlv| match tlv {
+            Ok(tlv) => C::decode(tlv.value.as_ref()).map_err(CodecError::Decode),
+            Err(error) => Err(CodecError::Parse(error)),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::v2::{Builder, Command, IPv4, ParseError, Protocol, Version, PROTOCOL_PREFIX};
+
+    #[derive(Debug, PartialEq)]
+    struct Deadline(u16);
+
+    impl TlvCodec for Deadline {
+        const KIND: u8 = 0xE1;
+        type Error = usize;
+
+        fn decode(value: &[u8]) -> Result<Self, Self::Error> {
+            match value {
+                [high, low] => Ok(Deadline(u16::from_be_bytes([*high, *low]))),
+                _ => Err(value.len()),
+            }
+        }
+
+        fn encode(&self) -> Vec<u8> {
+            self.0.to_be_bytes().to_vec()
+        }
+    }
+
+    impl From<Deadline> for u32 {
+        fn from(deadline: Deadline) -> Self {
+            deadline.0 as u32
+        }
+    }
+
+    impl TryFrom<&u32> for Deadline {
+        type Error = core::num::TryFromIntError;
+
+        fn try_from(deadline: &u32) -> Result<Self, Self::Error> {
+            u16::try_from(*deadline).map(Deadline)
+        }
+    }
+
+    /// A codec whose encoded value does not fit in a `TypeLengthValue`.
+    struct Oversized;
+
+    impl TlvCodec for Oversized {
+        const KIND: u8 = 0xE2;
+        type Error = ();
+
+        fn decode(_: &[u8]) -> Result<Self, Self::Error> {
+            Ok(Oversized)
+        }
+
+        fn encode(&self) -> Vec<u8> {
+            vec![0; u16::MAX as usize + 1]
+        }
+    }
+
+    impl TryFrom<&u32> for Oversized {
+        type Error = ();
+
+        fn try_from(_: &u32) -> Result<Self, Self::Error> {
+            Ok(Oversized)
+        }
+    }
+
+    fn header(tlvs: &[u8]) -> Vec<u8> {
+        Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .write_payload(tlvs)
+        .unwrap()
+        .build()
+        .unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let tlv = Deadline(500).to_tlv().unwrap();
+        let bytes = header(&[0xE1, 0, 2, 1, 244]);
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(tlv, TypeLengthValue::new(0xE1, &[1, 244]));
+        assert_eq!(header.decode_tlv::<Deadline>(), Some(Ok(Deadline(500))));
+
+        let encoded = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .write_payload(Encoded(Deadline(500)))
+        .unwrap()
+        .build()
+        .unwrap();
+
+        assert_eq!(encoded, bytes);
+    }
+
+    #[test]
+    fn oversized() {
+        let error = || BuildError::TlvTooLong {
+            kind: 0xE2,
+            len: u16::MAX as usize + 1,
+        };
+        let registry = TlvRegistry::<u32, ()>::new().register_encoder::<Oversized>();
+
+        assert_eq!(Oversized.to_tlv(), Err(error()));
+        assert_eq!(registry.encode(&1), Some(Err(error())));
+    }
+
+    #[test]
+    fn decode_errors() {
+        let invalid = header(&[0xE1, 0, 1, 1]);
+        let missing = header(&[]);
+        let malformed = header(&[0xE1, 0, 9]);
+
+        assert_eq!(
+            Header::try_from(invalid.as_slice())
+                .unwrap()
+                .decode_tlv::<Deadline>(),
+            Some(Err(CodecError::Decode(1)))
+        );
+        assert_eq!(
+            Header::try_from(missing.as_slice())
+                .unwrap()
+                .decode_tlv::<Deadline>(),
+            None
+        );
+        assert_eq!(
+            Header::try_from(malformed.as_slice())
+                .unwrap()
+                .decode_tlv::<Deadline>(),
+            Some(Err(CodecError::Parse(ParseError::InvalidTLV(0xE1, 9))))
+        );
+    }
+
+    #[test]
+    fn registry() {
+        let registry = TlvRegistry::<u32, usize>::new().register::<Deadline>();
+        let mut bytes = Vec::from(PROTOCOL_PREFIX);
+        bytes.extend([
+            0x21, 0x11, 0, 24, 127, 0, 0, 1, 192, 168, 1, 1, 0, 80, 1, 187,
+        ]);
+        bytes.extend([0xE1, 0, 2, 0, 1, 0xE2, 0, 0, 0xE1, 0, 1, 0]);
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert!(registry.is_registered(0xE1));
+        assert!(!registry.is_registered(0xE2));
+        assert_eq!(registry.decode(&TypeLengthValue::new(0xE2, &[])), None);
+        assert_eq!(
+            registry.decode_all(&header).collect::<Vec<_>>(),
+            vec![Ok(1), Err(CodecError::Decode(1))]
+        );
+        assert_eq!(
+            format!("{:?}", registry),
+            "TlvRegistry { kinds: [225], encoders: [] }"
+        );
+
+        let registry = registry.register_encoder::<Deadline>();
+
+        assert_eq!(
+            registry.encode(&500),
+            Some(Ok(TypeLengthValue::new(0xE1, &[1, 244])))
+        );
+        assert_eq!(registry.encode(&u32::MAX), None);
+    }
+}
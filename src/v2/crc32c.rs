@@ -0,0 +1,84 @@
+//! CRC-32C (Castagnoli) checksums for the `CRC32C` TLV.
+
+/// The reversed Castagnoli polynomial.
+const POLYNOMIAL: u32 = 0x82F6_3B78;
+
+/// Lookup table of the checksum of every byte value.
+const TABLE: [u32; 256] = table();
+
+const fn table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut index = 0;
+
+    while index < table.len() {
+        let mut crc = index as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+
+        table[index] = crc;
+        index += 1;
+    }
+
+    table
+}
+
+/// Calculates the CRC-32C checksum of the bytes.
+pub(crate) fn checksum(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, byte| {
+        TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Fills the 4-byte checksum fields starting at the given offsets with the big-endian checksum of the header,
+/// computed with every checksum field zeroed.
+pub(crate) fn seal(header: &mut [u8], offsets: &[usize]) {
+    for start in offsets {
+        header[*start..*start + 4].fill(0);
+    }
+
+    let checksum = checksum(header).to_be_bytes();
+
+    for start in offsets {
+        header[*start..*start + 4].copy_from_slice(checksum.as_slice());
+    }
+}
+
+/// Tests whether every 4-byte checksum field starting at the given offsets holds the checksum of the header.
+pub(crate) fn verify(header: &[u8], offsets: &[usize]) -> bool {
+    let mut sealed = header.to_vec();
+
+    seal(&mut sealed, offsets);
+
+    sealed == header
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value() {
+        assert_eq!(checksum(b""), 0);
+        assert_eq!(checksum(b"123456789"), 0xE306_9283);
+    }
+
+    #[test]
+    fn seal_and_verify() {
+        let mut header = *b"abc\xFF\xFF\xFF\xFFdef";
+
+        assert!(!verify(&header, &[3]));
+
+        seal(&mut header, &[3]);
+
+        assert!(verify(&header, &[3]));
+        assert_eq!(header[3..7], checksum(b"abc\0\0\0\0def").to_be_bytes());
+    }
+}
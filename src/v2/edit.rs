@@ -0,0 +1,233 @@
+//! Editing of existing PROXY protocol v2 headers.
+
+use crate::v2::crc32c;
+use crate::v2::{
+    Addresses, BuildError, Builder, Command, Header, ParseError, Protocol, Type, TypeLengthValue,
+    Version, MINIMUM_LENGTH, MINIMUM_TLV_LENGTH,
+};
+use alloc::borrow::Cow;
+
+/// An editable copy of a PROXY protocol v2 header.
+/// Addresses can be replaced and `TypeLengthValue`s added or removed before the header is built again.
+/// On `build`, the length is recomputed, as is the checksum of any `CRC32C` TLV.
+///
+/// ## Examples
+/// ```rust
+/// use ppp::v2::{Builder, Command, Header, HeaderMut, IPv4, Protocol, Type, Version};
+///
+/// let upstream = Builder::with_addresses(
+///     Version::Two | Command::Proxy,
+///     Protocol::Stream,
+///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+/// )
+/// .write_tlv(Type::UniqueId, b"upstream")
+/// .unwrap()
+/// .write_tlv(Type::NoOp, &[0; 3])
+/// .unwrap()
+/// .build()
+/// .unwrap();
+/// let header = Header::try_from(upstream.as_slice()).unwrap();
+///
+/// let mut edit = HeaderMut::try_from(&header).unwrap();
+/// edit.set_addresses(IPv4::new([10, 0, 0, 1], [10, 0, 0, 2], 1234, 443));
+/// edit.remove_tlv(Type::NoOp);
+/// edit.insert_tlv(Type::Authority, b"example.com".as_slice());
+///
+/// let bytes = edit.build().unwrap();
+/// let forwarded = Header::try_from(bytes.as_slice()).unwrap();
+///
+/// assert_eq!(forwarded.addresses, IPv4::new([10, 0, 0, 1], [10, 0, 0, 2], 1234, 443).into());
+/// assert_eq!(
+///     forwarded.tlvs().collect::<Result<Vec<_>, _>>().unwrap(),
+///     vec![
+///         (Type::UniqueId, b"upstream".as_slice()).into(),
+///         (Type::Authority, b"example.com".as_slice()).into(),
+///     ]
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeaderMut<'a> {
+    pub version: Version,
+    pub command: Command,
+    pub protocol: Protocol,
+    pub addresses: Addresses,
+    pub tlvs: Vec<TypeLengthValue<'a>>,
+}
+
+impl<'a> HeaderMut<'a> {
+    /// Replaces the addresses, which also determine the address family of the built header.
+    pub fn set_addresses<T: Into<Addresses>>(&mut self, addresses: T) {
+        self.addresses = addresses.into();
+    }
+
+    /// Appends a `TypeLengthValue` after the existing ones.
+    pub fn insert_tlv<T: Into<u8>, V: Into<Cow<'a, [u8]>>>(&mut self, kind: T, value: V) {
+        self.tlvs.push(TypeLengthValue {
+            kind: kind.into(),
+            value: value.into(),
+        });
+    }
+
+    /// Removes every `TypeLengthValue` of the given type.
+    /// Returns the number of `TypeLengthValue`s removed.
+    pub fn remove_tlv<T: Into<u8>>(&mut self, kind: T) -> usize {
+        let kind = kind.into();
+        let length = self.tlvs.len();
+
+        self.tlvs.retain(|tlv| tlv.kind != kind);
+
+        length - self.tlvs.len()
+    }
+
+    /// Retains only the `TypeLengthValue`s for which the predicate returns `true`.
+    pub fn retain_tlvs<F: FnMut(&TypeLengthValue<'a>) -> bool>(&mut self, predicate: F) {
+        self.tlvs.retain(predicate);
+    }
+
+    /// Builds the edited header.
+    /// The checksum of every 4-byte `CRC32C` TLV is recomputed over the built header.
+    pub fn build(&self) -> Result<Vec<u8>, BuildError> {
+        let mut header =
+            Builder::with_addresses(self.version | self.command, self.protocol, self.addresses)
+                .reserve_capacity(
+                    self.tlvs
+                        .iter()
+                        .map(|tlv| MINIMUM_TLV_LENGTH + tlv.len())
+                        .sum(),
+                )
+                .write_payloads(self.tlvs.iter())?
+                .build()?;
+
+        let crc = u8::from(Type::CRC32C);
+        let mut offset = MINIMUM_LENGTH + self.addresses.len();
+        let mut checksums = Vec::new();
+
+        for tlv in &self.tlvs {
+            if tlv.kind == crc && tlv.len() == 4 {
+                checksums.push(offset + MINIMUM_TLV_LENGTH);
+            }
+
+            offset += MINIMUM_TLV_LENGTH + tlv.len();
+        }
+
+        if !checksums.is_empty() {
+            crc32c::seal(&mut header, &checksums);
+        }
+
+        Ok(header)
+    }
+}
+
+impl<'a, 'b> TryFrom<&'a Header<'b>> for HeaderMut<'a> {
+    type Error = ParseError;
+
+    /// Copies the fixed portion and addresses of the header and borrows its `TypeLengthValue`s.
+    /// An error is returned if the `TypeLengthValue`s cannot be parsed.
+    fn try_from(header: &'a Header<'b>) -> Result<Self, Self::Error> {
+        Ok(HeaderMut {
+            version: header.version,
+            command: header.command,
+            protocol: header.protocol,
+            addresses: header.addresses,
+            tlvs: header.tlvs().collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::{IPv4, IPv6, PROTOCOL_PREFIX};
+
+    #[test]
+    fn round_trip() {
+        let input = Builder::with_addresses(
+            Version::Two | Command::Local,
+            Protocol::Datagram,
+            IPv6::new([1; 8], [2; 8], 80, 443),
+        )
+        .write_tlv(Type::SSL, &[1, 2, 3])
+        .unwrap()
+        .write_tlv(Type::Authority, b"example.com")
+        .unwrap()
+        .build()
+        .unwrap();
+        let header = Header::try_from(input.as_slice()).unwrap();
+
+        assert_eq!(
+            HeaderMut::try_from(&header).unwrap().build().unwrap(),
+            input
+        );
+    }
+
+    #[test]
+    fn retain_and_remove() {
+        let input = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .write_tlv(Type::NoOp, &[])
+        .unwrap()
+        .write_tlv(Type::SSL, &[])
+        .unwrap()
+        .write_tlv(Type::NoOp, &[0])
+        .unwrap()
+        .write_tlv(Type::UniqueId, &[1])
+        .unwrap()
+        .build()
+        .unwrap();
+        let header = Header::try_from(input.as_slice()).unwrap();
+        let mut edit = HeaderMut::try_from(&header).unwrap();
+
+        assert_eq!(edit.remove_tlv(Type::NoOp), 2);
+        assert_eq!(edit.remove_tlv(Type::NoOp), 0);
+
+        edit.retain_tlvs(|tlv| tlv.kind == u8::from(Type::UniqueId));
+
+        let mut expected = Vec::from(PROTOCOL_PREFIX);
+        expected.extend([
+            0x21, 0x11, 0, 16, 127, 0, 0, 1, 192, 168, 1, 1, 0, 80, 1, 187,
+        ]);
+        expected.extend([0x05, 0, 1, 1]);
+
+        assert_eq!(edit.build().unwrap(), expected);
+    }
+
+    #[test]
+    fn recompute_crc32c() {
+        let input = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv6::new([1; 8], [2; 8], 80, 443),
+        )
+        .write_tlv(Type::CRC32C, &[0xFF; 4])
+        .unwrap()
+        .build()
+        .unwrap();
+        let header = Header::try_from(input.as_slice()).unwrap();
+        let mut edit = HeaderMut::try_from(&header).unwrap();
+
+        edit.insert_tlv(Type::Authority, b"example.com".as_slice());
+
+        let mut output = edit.build().unwrap();
+        let start = MINIMUM_LENGTH + edit.addresses.len() + MINIMUM_TLV_LENGTH;
+        let checksum = u32::from_be_bytes(output[start..start + 4].try_into().unwrap());
+
+        output[start..start + 4].fill(0);
+
+        assert_eq!(checksum, crc32c::checksum(output.as_slice()));
+    }
+
+    #[test]
+    fn invalid_tlvs() {
+        let mut input = Vec::from(PROTOCOL_PREFIX);
+        input.extend([
+            0x21, 0x11, 0, 14, 127, 0, 0, 1, 192, 168, 1, 1, 0, 80, 1, 187,
+        ]);
+        input.extend([0x05, 0]);
+        let header = Header::try_from(input.as_slice()).unwrap();
+
+        assert_eq!(HeaderMut::try_from(&header), Err(ParseError::Leftovers(2)));
+    }
+}
@@ -1,7 +1,12 @@
 //! Errors for the binary proxy protocol.
 
+use crate::v2::AddressFamily;
+#[cfg(feature = "std")]
+use std::io;
+
 /// An error in parsing a binary PROXY protocol header.
 #[derive(thiserror::Error, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum ParseError {
     #[error("Expected header to the protocol prefix plus 4 bytes after the prefix (length {0}).")]
     Incomplete(usize),
@@ -27,4 +32,130 @@ pub enum ParseError {
     InvalidTLV(u8, u16),
     #[error("Header contains leftover {0} bytes not accounted for by the address family or TLVs.")]
     Leftovers(usize),
+    #[error("Version 2 headers are disabled by the parse options.")]
+    Disabled,
+    #[error("Header has the Local command, which is denied by the parse options.")]
+    LocalCommand,
+    #[error(
+        "Header length of {0} bytes exceeds the maximum of {1} bytes allowed by the parse options."
+    )]
+    PayloadTooLong(usize, usize),
+    #[error("Header does not contain any TLVs, which are required by the parse options.")]
+    MissingTLVs,
+    #[error("Header contains {0} bytes of TLVs, which are forbidden by the parse options.")]
+    UnexpectedTLVs(usize),
+    #[error(
+        "TLV at offset {offset} requires {required} bytes, but only {available} bytes remain."
+    )]
+    MalformedTLV {
+        offset: usize,
+        required: usize,
+        available: usize,
+    },
+    #[error("Unknown TLV type {0:#X}.")]
+    UnknownType(u8),
+    #[error("TLV at offset {offset} has type {kind:#X}, which is rejected by the parse options.")]
+    RejectedTLV { offset: usize, kind: u8 },
+    #[error("Unique ID of {0} bytes exceeds the maximum of 128 bytes.")]
+    UniqueIdTooLong(usize),
+    #[error("Network namespace name is not valid UTF-8: {0}")]
+    NetworkNamespaceUtf8(core::str::Utf8Error),
+    #[error("Network namespace name must be 1 to 255 bytes, must not be '.' or '..' and must not contain '/' or NUL.")]
+    InvalidNetworkNamespace,
+}
+
+/// An error in decoding a `TypeLengthValue` with a `TlvCodec`.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum CodecError<E> {
+    #[error(transparent)]
+    Parse(ParseError),
+    #[error("Failed to decode TLV value: {0}")]
+    Decode(E),
+}
+
+/// An error in verifying the signature TLV of a header.
+#[cfg(feature = "hmac")]
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum SignatureError {
+    #[error(transparent)]
+    Parse(ParseError),
+    #[error("Header does not contain a signature TLV.")]
+    Missing,
+    #[error("Signature TLV has {0} bytes, but expected 44 bytes.")]
+    Malformed(usize),
+    #[error("Signature was created with unknown key {0}.")]
+    UnknownKey(u32),
+    #[error("Signature does not match the header.")]
+    Mismatch,
+    #[error("Signature timestamp {timestamp} is too far from the current time {now}.")]
+    Stale { timestamp: u64, now: u64 },
+}
+
+/// An error in building a binary PROXY protocol header.
+/// Converts to and from `io::Error`, so `?` works in functions returning either type.
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum BuildError {
+    #[error("Buffer of {available} bytes cannot hold the {required} bytes of the header.")]
+    Capacity { required: usize, available: usize },
+    #[error("TLV {kind:#X} has a value of {len} bytes, which exceeds the maximum of 65535 bytes.")]
+    TlvTooLong { kind: u8, len: usize },
+    #[error("Payload of {0} bytes exceeds the maximum length of 65535 bytes.")]
+    PayloadTooLong(usize),
+    #[error("Expected addresses for the {expected:?} address family, but found {actual:?}.")]
+    AddressFamilyMismatch {
+        expected: AddressFamily,
+        actual: AddressFamily,
+    },
+    #[error("Built header is not a valid PROXY protocol v2 header: {0}")]
+    Invalid(ParseError),
+    #[error("Cannot pad a header of {length} bytes to {target} bytes with a NoOp TLV of at least 3 bytes.")]
+    Padding { length: usize, target: usize },
+    #[cfg(feature = "hmac")]
+    #[error(
+        "Headers without addresses cannot be signed, since their payload is not parsed as TLVs."
+    )]
+    SignatureWithoutAddresses,
+    #[cfg(feature = "hmac")]
+    #[error("Header already contains a TLV of the signature type {0:#X}.")]
+    DuplicateSignature(u8),
+    #[cfg(feature = "hmac")]
+    #[error("Templates cannot contain a signature TLV, since it would not match the rendered addresses.")]
+    SignedTemplate,
+    #[cfg(feature = "std")]
+    #[error("Failed to write the header: {0}.")]
+    Io(io::ErrorKind),
+}
+
+#[cfg(feature = "std")]
+impl From<BuildError> for io::Error {
+    fn from(error: BuildError) -> Self {
+        let kind = match error {
+            BuildError::Io(kind) => return kind.into(),
+            BuildError::AddressFamilyMismatch { .. } => io::ErrorKind::InvalidInput,
+            #[cfg(feature = "hmac")]
+            BuildError::SignatureWithoutAddresses
+            | BuildError::DuplicateSignature(_)
+            | BuildError::SignedTemplate => io::ErrorKind::InvalidInput,
+            BuildError::Invalid(_) => io::ErrorKind::InvalidData,
+            _ => io::ErrorKind::WriteZero,
+        };
+
+        io::Error::new(kind, error)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for BuildError {
+    /// Recovers the `BuildError` wrapped by an `io::Error`, or keeps only the `io::ErrorKind` of any other error.
+    fn from(error: io::Error) -> Self {
+        let kind = error.kind();
+
+        match error
+            .into_inner()
+            .map(|inner| inner.downcast::<BuildError>())
+        {
+            Some(Ok(error)) => *error,
+            _ => BuildError::Io(kind),
+        }
+    }
 }
@@ -0,0 +1,329 @@
+//! Allocation-free builder for PROXY protocol v2 headers backed by a fixed-capacity buffer.
+
+use crate::v2::{
+    Addresses, BuildError, Protocol, LENGTH, MINIMUM_LENGTH, MINIMUM_TLV_LENGTH, PROTOCOL_PREFIX,
+};
+
+/// Implementation of the builder pattern for PROXY protocol v2 headers that writes into a caller-provided buffer.
+/// The buffer may be a mutable byte slice or an owned array (i.e. `[u8; N]`).
+/// Unlike `Builder`, no allocations are made and running out of space results in a `BuildError::Capacity`.
+///
+/// ## Examples
+/// ```rust
+/// use ppp::v2::{Addresses, FixedBuilder, Command, IPv4, Protocol, PROTOCOL_PREFIX, Type, Version};
+/// let mut expected = Vec::from(PROTOCOL_PREFIX);
+/// expected.extend([
+///    0x21, 0x12, 0, 16, 127, 0, 0, 1, 192, 168, 1, 1, 0, 80, 1, 187, 4, 0, 1, 42
+/// ]);
+///
+/// let addresses: Addresses = IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443).into();
+/// let mut builder = FixedBuilder::with_addresses(
+///     [0; 64],
+///     Version::Two | Command::Proxy,
+///     Protocol::Datagram,
+///     addresses
+/// )
+/// .unwrap()
+/// .write_tlv(Type::NoOp, [42].as_slice())
+/// .unwrap();
+///
+/// assert_eq!(builder.build().unwrap(), expected.as_slice());
+/// ```
+#[derive(Debug)]
+pub struct FixedBuilder<B> {
+    buffer: B,
+    length: usize,
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> FixedBuilder<B> {
+    /// Creates an instance of a `FixedBuilder` with the given header bytes.
+    /// No guarantee is made that any address bytes written as a payload will match the header's address family.
+    pub fn new(
+        buffer: B,
+        version_command: u8,
+        address_family_protocol: u8,
+    ) -> Result<Self, BuildError> {
+        let mut builder = FixedBuilder { buffer, length: 0 };
+
+        builder.reserve(MINIMUM_LENGTH)?;
+
+        let header = builder.buffer.as_mut();
+
+        header[..PROTOCOL_PREFIX.len()].copy_from_slice(PROTOCOL_PREFIX);
+        header[PROTOCOL_PREFIX.len()] = version_command;
+        header[PROTOCOL_PREFIX.len() + 1] = address_family_protocol;
+        header[LENGTH..MINIMUM_LENGTH].fill(0);
+
+        builder.length = MINIMUM_LENGTH;
+
+        Ok(builder)
+    }
+
+    /// Creates an instance of a `FixedBuilder` with the given header bytes and `Addresses`.
+    /// The address family is determined from the variant of the `Addresses` given.
+    pub fn with_addresses<T: Into<Addresses>>(
+        buffer: B,
+        version_command: u8,
+        protocol: Protocol,
+        addresses: T,
+    ) -> Result<Self, BuildError> {
+        let addresses = addresses.into();
+
+        FixedBuilder::new(
+            buffer,
+            version_command,
+            addresses.address_family() | protocol,
+        )?
+        .write_addresses(&addresses)
+    }
+
+    /// Writes the bytes of the given `Addresses` as a payload.
+    pub fn write_addresses(mut self, addresses: &Addresses) -> Result<Self, BuildError> {
+        let start = self.reserve(addresses.len())?;
+
+        write_addresses(&mut self.buffer.as_mut()[start..], addresses);
+
+        Ok(self)
+    }
+
+    /// Writes a single payload to the buffer.
+    /// No surrounding bytes (terminal or otherwise) are added by this `FixedBuilder`.
+    pub fn write_payload(mut self, payload: &[u8]) -> Result<Self, BuildError> {
+        let start = self.reserve(payload.len())?;
+
+        self.buffer.as_mut()[start..start + payload.len()].copy_from_slice(payload);
+
+        Ok(self)
+    }
+
+    /// Writes a Type-Length-Value as a payload.
+    /// The length is determined by the length of the slice.
+    /// An error is returned when the length of the slice exceeds `u16::MAX`.
+    pub fn write_tlv(mut self, kind: impl Into<u8>, value: &[u8]) -> Result<Self, BuildError> {
+        let kind = kind.into();
+        let length = u16::try_from(value.len()).map_err(|_| BuildError::TlvTooLong {
+            kind,
+            len: value.len(),
+        })?;
+        let start = self.reserve(MINIMUM_TLV_LENGTH + value.len())?;
+        let tlv = &mut self.buffer.as_mut()[start..start + MINIMUM_TLV_LENGTH + value.len()];
+
+        tlv[0] = kind;
+        tlv[1..MINIMUM_TLV_LENGTH].copy_from_slice(length.to_be_bytes().as_slice());
+        tlv[MINIMUM_TLV_LENGTH..].copy_from_slice(value);
+
+        Ok(self)
+    }
+
+    /// The number of bytes written to the buffer so far.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Tests whether any bytes have been written to the buffer.
+    /// Always false, since the fixed portion of the header is written on creation.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Writes the length of the payload to the header and returns the header bytes.
+    /// Returns an error when the length of the payload portion exceeds `u16::MAX`.
+    pub fn build(&mut self) -> Result<&[u8], BuildError> {
+        let payload_length = self.length - MINIMUM_LENGTH;
+        let length = u16::try_from(payload_length)
+            .map_err(|_| BuildError::PayloadTooLong(payload_length))?;
+        let header = &mut self.buffer.as_mut()[..self.length];
+
+        header[LENGTH..MINIMUM_LENGTH].copy_from_slice(length.to_be_bytes().as_slice());
+
+        Ok(header)
+    }
+
+    /// Consumes this `FixedBuilder` and returns the underlying buffer.
+    pub fn into_inner(self) -> B {
+        self.buffer
+    }
+
+    /// Reserves the given number of bytes at the end of the written portion of the buffer.
+    /// Returns the offset of the start of the reserved bytes.
+    fn reserve(&mut self, additional: usize) -> Result<usize, BuildError> {
+        let start = self.length;
+        let required = start + additional;
+        let available = self.buffer.as_ref().len();
+
+        if required > available {
+            return Err(BuildError::Capacity {
+                required,
+                available,
+            });
+        }
+
+        self.length = required;
+
+        Ok(start)
+    }
+}
+
+/// Writes the bytes of the `Addresses` to the start of the buffer.
+/// The buffer must be at least `addresses.len()` bytes long.
+pub(crate) fn write_addresses(buffer: &mut [u8], addresses: &Addresses) {
+    match addresses {
+        Addresses::Unspecified => (),
+        Addresses::IPv4(a) => {
+            buffer[..4].copy_from_slice(a.source_address.octets().as_slice());
+            buffer[4..8].copy_from_slice(a.destination_address.octets().as_slice());
+            buffer[8..10].copy_from_slice(a.source_port.to_be_bytes().as_slice());
+            buffer[10..12].copy_from_slice(a.destination_port.to_be_bytes().as_slice());
+        }
+        Addresses::IPv6(a) => {
+            buffer[..16].copy_from_slice(a.source_address.octets().as_slice());
+            buffer[16..32].copy_from_slice(a.destination_address.octets().as_slice());
+            buffer[32..34].copy_from_slice(a.source_port.to_be_bytes().as_slice());
+            buffer[34..36].copy_from_slice(a.destination_port.to_be_bytes().as_slice());
+        }
+        Addresses::Unix(a) => {
+            buffer[..108].copy_from_slice(a.source.as_slice());
+            buffer[108..216].copy_from_slice(a.destination.as_slice());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::v2::{AddressFamily, Builder, Command, IPv4, IPv6, Type, Unix, Version};
+
+    #[test]
+    fn build_matches_builder() {
+        let addresses: Addresses = IPv6::new([0xFFFF; 8], [0xFFF1; 8], 80, 443).into();
+        let expected = Builder::with_addresses(
+            Version::Two | Command::Local,
+            Protocol::Unspecified,
+            addresses,
+        )
+        .write_tlv(Type::NoOp, [0].as_slice())
+        .unwrap()
+        .write_tlv(Type::Authority, b"example.com")
+        .unwrap()
+        .build()
+        .unwrap();
+
+        let mut buffer = [0xAA; 128];
+        let mut builder = FixedBuilder::with_addresses(
+            buffer.as_mut_slice(),
+            Version::Two | Command::Local,
+            Protocol::Unspecified,
+            addresses,
+        )
+        .unwrap()
+        .write_tlv(Type::NoOp, [0].as_slice())
+        .unwrap()
+        .write_tlv(Type::Authority, b"example.com")
+        .unwrap();
+
+        assert_eq!(builder.len(), expected.len());
+        assert_eq!(builder.build().unwrap(), expected.as_slice());
+        assert_eq!(&buffer[..expected.len()], expected.as_slice());
+        assert_eq!(buffer[expected.len()], 0xAA);
+    }
+
+    #[test]
+    fn build_unix_array() {
+        let addresses: Addresses = Unix::new([0xFF; 108], [0xAA; 108]).into();
+        let mut expected = Vec::from(PROTOCOL_PREFIX);
+        expected.extend([0x20, 0x31, 0, 216]);
+        expected.extend([0xFF; 108]);
+        expected.extend([0xAA; 108]);
+
+        let mut builder = FixedBuilder::new(
+            [0; 232],
+            Version::Two | Command::Local,
+            AddressFamily::Unix | Protocol::Stream,
+        )
+        .unwrap()
+        .write_addresses(&addresses)
+        .unwrap();
+
+        assert_eq!(builder.build().unwrap(), expected.as_slice());
+        assert_eq!(builder.into_inner().as_slice(), expected.as_slice());
+    }
+
+    #[test]
+    fn build_arbitrary_payload() {
+        let mut expected = Vec::from(PROTOCOL_PREFIX);
+        expected.extend([0x21, 0x01, 0, 1, 42]);
+
+        let mut builder = FixedBuilder::new(
+            [0; 17],
+            Version::Two | Command::Proxy,
+            AddressFamily::Unspecified | Protocol::Stream,
+        )
+        .unwrap()
+        .write_payload(&[42])
+        .unwrap();
+
+        assert_eq!(builder.build().unwrap(), expected.as_slice());
+    }
+
+    #[test]
+    fn capacity_too_small() {
+        let error = FixedBuilder::with_addresses(
+            [0; 20],
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [127, 0, 0, 2], 80, 443),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            error,
+            BuildError::Capacity {
+                required: 28,
+                available: 20
+            }
+        );
+
+        let error = FixedBuilder::new([0; 8], 0x21, 0x11).unwrap_err();
+
+        assert_eq!(
+            error,
+            BuildError::Capacity {
+                required: 16,
+                available: 8
+            }
+        );
+    }
+
+    #[test]
+    fn tlv_too_long() {
+        let value = vec![0; (u16::MAX as usize) + 1];
+        let error = FixedBuilder::new(vec![0; 2 * value.len()], 0x21, 0x01)
+            .unwrap()
+            .write_tlv(Type::NoOp, value.as_slice())
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            BuildError::TlvTooLong {
+                kind: Type::NoOp.into(),
+                len: value.len()
+            }
+        );
+    }
+
+    #[test]
+    fn payload_too_long() {
+        let value = vec![0; u16::MAX as usize];
+        let error = FixedBuilder::new(vec![0; 2 * value.len()], 0x21, 0x01)
+            .unwrap()
+            .write_tlv(Type::NoOp, value.as_slice())
+            .unwrap()
+            .build()
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            BuildError::PayloadTooLong(MINIMUM_TLV_LENGTH + value.len())
+        );
+    }
+}
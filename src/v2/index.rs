@@ -0,0 +1,238 @@
+//! Indexed lookup of the `TypeLengthValue`s in a header.
+
+use crate::v2::{Header, ParseError, TypeLengthValue, TypeLengthValues, MINIMUM_TLV_LENGTH};
+use alloc::vec::Vec;
+
+/// The validated `TypeLengthValue`s of a header, indexed by type.
+/// Every `TypeLengthValue` is parsed once up front, so lookups neither re-scan the header nor return errors.
+///
+/// ## Examples
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use ppp::v2::{Builder, Command, Header, IPv4, Protocol, TlvIndex, Type, Version};
+///
+/// let bytes = Builder::with_addresses(
+///     Version::Two | Command::Proxy,
+///     Protocol::Stream,
+///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+/// )
+/// .write_tlv(Type::UniqueId, b"abc")
+/// .unwrap()
+/// .write_tlv(Type::Authority, b"example.com")
+/// .unwrap()
+/// .build()
+/// .unwrap();
+/// let header = Header::try_from(bytes.as_slice()).unwrap();
+/// let index = TlvIndex::try_from(&header).unwrap();
+///
+/// assert_eq!(index.get(Type::Authority).unwrap().value.as_ref(), b"example.com");
+/// assert_eq!(index.get(Type::UniqueId).unwrap().value.as_ref(), b"abc");
+/// assert!(index.get(Type::SSL).is_none());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TlvIndex<'a> {
+    bytes: &'a [u8],
+    /// The type and offset of each `TypeLengthValue`, sorted by type and then by offset.
+    entries: Vec<(u8, usize)>,
+}
+
+impl<'a> TlvIndex<'a> {
+    /// The first `TypeLengthValue` of the given type.
+    pub fn get<T: Into<u8>>(&self, kind: T) -> Option<TypeLengthValue<'a>> {
+        self.get_all(kind).next()
+    }
+
+    /// An `Iterator` of the `TypeLengthValue`s of the given type, in the order they appear in the header.
+    pub fn get_all<T: Into<u8>>(&self, kind: T) -> impl Iterator<Item = TypeLengthValue<'a>> + '_ {
+        let kind = kind.into();
+        let start = self.entries.partition_point(|(k, _)| *k < kind);
+
+        self.entries[start..]
+            .iter()
+            .take_while(move |(k, _)| *k == kind)
+            .map(|(_, offset)| self.at(*offset))
+    }
+
+    /// Tests whether a `TypeLengthValue` of the given type is present.
+    pub fn contains<T: Into<u8>>(&self, kind: T) -> bool {
+        self.get(kind).is_some()
+    }
+
+    /// The number of `TypeLengthValue`s in the header.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Tests whether the header has no `TypeLengthValue`s.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The `TypeLengthValue` starting at the given offset, which is known to be valid.
+    fn at(&self, offset: usize) -> TypeLengthValue<'a> {
+        let bytes = self.bytes;
+        let length = u16::from_be_bytes([bytes[offset + 1], bytes[offset + 2]]) as usize;
+        let start = offset + MINIMUM_TLV_LENGTH;
+
+        TypeLengthValue::new(bytes[offset], &bytes[start..start + length])
+    }
+}
+
+impl<'a> TryFrom<TypeLengthValues<'a>> for TlvIndex<'a> {
+    type Error = ParseError;
+
+    /// Parses every `TypeLengthValue` the iterator yields, so a partly consumed iterator
+    /// or one that skips `NoOp`s only indexes the remaining `TypeLengthValue`s.
+    /// An error is returned if any `TypeLengthValue` is malformed.
+    fn try_from(mut tlvs: TypeLengthValues<'a>) -> Result<Self, Self::Error> {
+        let bytes = tlvs.as_bytes();
+        let mut entries = Vec::new();
+
+        while let Some(tlv) = tlvs.next() {
+            let tlv = tlv?;
+            // The iterator has just moved past this `TypeLengthValue`.
+            let offset = tlvs.offset() - MINIMUM_TLV_LENGTH - tlv.len();
+
+            entries.push((tlv.kind, offset));
+        }
+
+        entries.sort_unstable();
+
+        Ok(TlvIndex { bytes, entries })
+    }
+}
+
+impl<'a, 'b> TryFrom<&'a Header<'b>> for TlvIndex<'a> {
+    type Error = ParseError;
+
+    fn try_from(header: &'a Header<'b>) -> Result<Self, Self::Error> {
+        TlvIndex::try_from(header.tlvs())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::v2::{Builder, Command, IPv4, Protocol, Type, Version, PROTOCOL_PREFIX};
+
+    #[test]
+    fn repeated() {
+        let bytes = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .write_tlv(Type::NoOp, &[2])
+        .unwrap()
+        .write_tlv(Type::ALPN, b"h2")
+        .unwrap()
+        .write_tlv(Type::NoOp, &[1])
+        .unwrap()
+        .write_tlv(Type::NoOp, &[])
+        .unwrap()
+        .build()
+        .unwrap();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+        let index = TlvIndex::try_from(&header).unwrap();
+
+        assert_eq!(index.len(), 4);
+        assert!(index.contains(Type::ALPN));
+        assert!(!index.contains(Type::Authority));
+        assert_eq!(
+            index.get_all(Type::NoOp).collect::<Vec<_>>(),
+            header
+                .tlvs_of(Type::NoOp)
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        );
+        assert_eq!(
+            index.get(Type::NoOp),
+            Some((Type::NoOp, [2].as_slice()).into())
+        );
+    }
+
+    #[test]
+    fn skip_noop_and_partly_consumed() {
+        let bytes = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .write_tlv(Type::NoOp, &[0; 5])
+        .unwrap()
+        .write_tlv(Type::ALPN, b"h2")
+        .unwrap()
+        .write_tlv(Type::Authority, b"example.com")
+        .unwrap()
+        .build()
+        .unwrap();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+        let index = TlvIndex::try_from(header.tlvs().skip_noop(true)).unwrap();
+
+        assert_eq!(index.len(), 2);
+        assert!(!index.contains(Type::NoOp));
+        assert_eq!(
+            index.get(Type::Authority),
+            Some((Type::Authority, b"example.com".as_slice()).into())
+        );
+        assert_eq!(
+            index.get(Type::ALPN),
+            Some((Type::ALPN, b"h2".as_slice()).into())
+        );
+
+        let mut tlvs = header.tlvs();
+
+        tlvs.next();
+
+        let index = TlvIndex::try_from(tlvs).unwrap();
+
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.get(Type::Authority),
+            Some((Type::Authority, b"example.com".as_slice()).into())
+        );
+    }
+
+    #[test]
+    fn empty() {
+        let bytes = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .build()
+        .unwrap();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+        let index = TlvIndex::try_from(&header).unwrap();
+
+        assert!(index.is_empty());
+        assert!(index.get(Type::NoOp).is_none());
+    }
+
+    #[test]
+    fn invalid() {
+        let mut bytes = Vec::from(PROTOCOL_PREFIX);
+        bytes.extend([
+            0x21, 0x11, 0, 19, 127, 0, 0, 1, 192, 168, 1, 1, 0, 80, 1, 187,
+        ]);
+        bytes.extend([0x02, 0, 1, b'a', 0x05, 0, 2]);
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            TlvIndex::try_from(&header),
+            Err(ParseError::InvalidTLV(0x05, 2))
+        );
+        assert_eq!(
+            header.tlv(Type::Authority),
+            Some(Ok((Type::Authority, b"a".as_slice()).into()))
+        );
+        assert_eq!(
+            header.tlv(Type::UniqueId),
+            Some(Err(ParseError::InvalidTLV(0x05, 2)))
+        );
+        assert_eq!(
+            header.tlvs_of(Type::NoOp).collect::<Vec<_>>(),
+            vec![Err(ParseError::InvalidTLV(0x05, 2))]
+        );
+    }
+}
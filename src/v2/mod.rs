@@ -2,20 +2,62 @@
 //!
 //! See <https://haproxy.org/download/1.8/doc/proxy-protocol.txt>
 
+#[cfg(feature = "bytes")]
+mod buf;
+#[cfg(feature = "std")]
 mod builder;
+#[cfg(feature = "alloc")]
+mod canonical;
+#[cfg(feature = "alloc")]
+mod codec;
+#[cfg(feature = "alloc")]
+mod crc32c;
+#[cfg(feature = "std")]
+mod edit;
 mod error;
+mod fixed;
+#[cfg(feature = "alloc")]
+mod index;
 mod model;
+mod netns;
+#[cfg(feature = "hmac")]
+mod signature;
+#[cfg(feature = "std")]
+mod template;
+mod unique_id;
 
 pub use crate::ip::{IPv4, IPv6};
-pub use builder::{Builder, WriteToHeader, Writer};
-pub use error::ParseError;
+#[cfg(feature = "alloc")]
+use crate::{scatter, PartialResult};
+use crate::{ParseOptions, TlvPolicy};
+#[cfg(feature = "bytes")]
+pub use buf::OwnedHeader;
+#[cfg(feature = "std")]
+pub use builder::{Builder, CheckedBuilder, WriteToHeader, Writer};
+#[cfg(feature = "alloc")]
+pub use codec::{Encoded, TlvCodec, TlvRegistry};
+use core::net::{Ipv4Addr, Ipv6Addr};
+#[cfg(feature = "std")]
+pub use edit::HeaderMut;
+#[cfg(feature = "hmac")]
+pub use error::SignatureError;
+pub use error::{BuildError, CodecError, ParseError};
+pub use fixed::FixedBuilder;
+#[cfg(feature = "alloc")]
+pub use index::TlvIndex;
 pub use model::{
-    AddressFamily, Addresses, Command, Header, Protocol, Type, TypeLengthValue, TypeLengthValues,
-    Unix, Version, PROTOCOL_PREFIX,
+    AddressFamily, Addresses, Command, Header, LenientTypeLengthValues, MalformedRegion, Protocol,
+    TlvKind, Type, TypeLengthValue, TypeLengthValues, Unix, Version, PROTOCOL_PREFIX,
 };
-use model::{MINIMUM_LENGTH, MINIMUM_TLV_LENGTH};
-use std::borrow::Cow;
-use std::net::{Ipv4Addr, Ipv6Addr};
+pub(crate) use model::{MINIMUM_LENGTH, MINIMUM_TLV_LENGTH};
+pub use netns::{NetworkNamespace, MAX_NETWORK_NAMESPACE_LENGTH};
+#[cfg(feature = "hmac")]
+pub use signature::{Signature, Signer, Verifier, SIGNATURE_LENGTH, SIGNATURE_TLV};
+#[cfg(feature = "std")]
+pub use template::HeaderTemplate;
+#[cfg(feature = "std")]
+pub use unique_id::StdIdSource;
+pub use unique_id::{IdSource, UniqueId, UniqueIdFormat, UniqueIdGenerator, MAX_UNIQUE_ID_LENGTH};
 
 /// Masks the right 4-bits so only the left 4-bits are present.
 const LEFT_MASK: u8 = 0xF0;
@@ -79,10 +121,107 @@ fn parse_addresses(address_family: AddressFamily, bytes: &[u8]) -> Addresses {
     }
 }
 
+/// The number of bytes before the nested `TypeLengthValue`s in the value of an `SSL` TLV (client and verify fields).
+const SSL_FIXED_LENGTH: usize = 5;
+
+/// Validates every `TypeLengthValue` in `header[start..]`, recursing into the value of `SSL` TLVs.
+/// Each `TypeLengthValue`'s type must be allowed by the options.
+/// Offsets in errors are relative to the start of the header.
+fn validate_tlvs(header: &[u8], start: usize, options: &ParseOptions) -> Result<(), ParseError> {
+    let mut offset = start;
+
+    while offset < header.len() {
+        let available = header.len() - offset;
+
+        if available < MINIMUM_TLV_LENGTH {
+            return Err(ParseError::MalformedTLV {
+                offset,
+                required: MINIMUM_TLV_LENGTH,
+                available,
+            });
+        }
+
+        let length = u16::from_be_bytes([header[offset + 1], header[offset + 2]]) as usize;
+        let required = MINIMUM_TLV_LENGTH + length;
+
+        if available < required {
+            return Err(ParseError::MalformedTLV {
+                offset,
+                required,
+                available,
+            });
+        }
+
+        let kind = header[offset];
+
+        if !options.allows(TlvKind::classify(kind)) {
+            return Err(ParseError::RejectedTLV { offset, kind });
+        }
+
+        if kind == Type::SSL as u8 {
+            if length < SSL_FIXED_LENGTH {
+                return Err(ParseError::MalformedTLV {
+                    offset,
+                    required: MINIMUM_TLV_LENGTH + SSL_FIXED_LENGTH,
+                    available: required,
+                });
+            }
+
+            let value = offset + MINIMUM_TLV_LENGTH;
+
+            validate_tlvs(
+                &header[..offset + required],
+                value + SSL_FIXED_LENGTH,
+                options,
+            )?;
+        }
+
+        offset += required;
+    }
+
+    Ok(())
+}
+
 impl<'a> TryFrom<&'a [u8]> for Header<'a> {
     type Error = ParseError;
 
     fn try_from(input: &'a [u8]) -> Result<Self, Self::Error> {
+        Header::parse_with(input, &ParseOptions::new())
+    }
+}
+
+impl<'a> Header<'a> {
+    /// Parses a header from the start of the bytes with the given `ParseOptions`.
+    /// Every option is checked as soon as the fixed 16 bytes of the header are available,
+    /// so a header that violates the options is rejected before its payload is read.
+    ///
+    /// ## Examples
+    #[cfg_attr(feature = "std", doc = "```rust")]
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
+    /// use ppp::{ParseOptions, TlvPolicy};
+    /// use ppp::v2::{Builder, Command, Header, IPv4, ParseError, Protocol, Type, Version};
+    ///
+    /// let bytes = Builder::with_addresses(
+    ///     Version::Two | Command::Proxy,
+    ///     Protocol::Stream,
+    ///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+    /// )
+    /// .write_tlv(Type::NoOp, [0; 100].as_slice())
+    /// .unwrap()
+    /// .build()
+    /// .unwrap();
+    ///
+    /// let options = ParseOptions::default().max_v2_payload(64);
+    /// assert_eq!(Header::parse_with(&bytes[..16], &options), Err(ParseError::PayloadTooLong(115, 64)));
+    ///
+    /// let options = ParseOptions::default().tlvs(TlvPolicy::Forbid);
+    /// assert_eq!(Header::parse_with(&bytes, &options), Err(ParseError::UnexpectedTLVs(103)));
+    /// ```
+    pub fn parse_with(input: &'a [u8], options: &ParseOptions) -> Result<Self, ParseError> {
+        if !options.v2 {
+            return Err(ParseError::Disabled);
+        }
+
         if input.len() < PROTOCOL_PREFIX.len() {
             if PROTOCOL_PREFIX.starts_with(input) {
                 return Err(ParseError::Incomplete(input.len()));
@@ -109,6 +248,10 @@ impl<'a> TryFrom<&'a [u8]> for Header<'a> {
             c => return Err(ParseError::Command(c)),
         };
 
+        if command == Command::Local && !options.allow_local {
+            return Err(ParseError::LocalCommand);
+        }
+
         let address_family = match input[ADDRESS_FAMILY_PROTOCOL] & LEFT_MASK {
             0x00 => AddressFamily::Unspecified,
             0x10 => AddressFamily::IPv4,
@@ -130,6 +273,23 @@ impl<'a> TryFrom<&'a [u8]> for Header<'a> {
             return Err(ParseError::InvalidAddresses(length, address_family_bytes));
         }
 
+        if length > options.max_v2_payload as usize {
+            return Err(ParseError::PayloadTooLong(
+                length,
+                options.max_v2_payload as usize,
+            ));
+        }
+
+        let tlv_length = length - address_family.byte_length().unwrap_or(length);
+
+        match options.tlvs {
+            TlvPolicy::Require if tlv_length == 0 => return Err(ParseError::MissingTLVs),
+            TlvPolicy::Forbid if tlv_length > 0 => {
+                return Err(ParseError::UnexpectedTLVs(tlv_length))
+            }
+            _ => (),
+        }
+
         let full_length = MINIMUM_LENGTH + length;
 
         if input.len() < full_length {
@@ -137,25 +297,96 @@ impl<'a> TryFrom<&'a [u8]> for Header<'a> {
         }
 
         let header = &input[..full_length];
+
+        if options.walk_tlvs() {
+            validate_tlvs(
+                header,
+                MINIMUM_LENGTH + address_family.byte_length().unwrap_or(length),
+                options,
+            )?;
+        }
+
         let addresses = parse_addresses(
             address_family,
             &header[MINIMUM_LENGTH..MINIMUM_LENGTH + address_family_bytes],
         );
 
         Ok(Header {
-            header: Cow::Borrowed(header),
+            header: crate::borrowed(header),
             version,
             command,
             protocol,
             addresses,
         })
     }
+
+    /// Parses a header from a list of slices (i.e. `&[&[u8]]` or `&[IoSlice]`), as if the slices were contiguous.
+    /// The header borrows from the input when it is entirely contained in the first non-empty slice.
+    /// Otherwise, only the bytes of the header are copied into an owned header.
+    ///
+    /// ## Examples
+    #[cfg_attr(feature = "std", doc = "```rust")]
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
+    /// use ppp::v2::{Builder, Command, Header, IPv4, Protocol, Version};
+    /// use std::io::IoSlice;
+    ///
+    /// let bytes = Builder::with_addresses(
+    ///     Version::Two | Command::Proxy,
+    ///     Protocol::Stream,
+    ///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+    /// )
+    /// .build()
+    /// .unwrap();
+    /// let (left, right) = bytes.split_at(10);
+    /// let slices = [IoSlice::new(left), IoSlice::new(right)];
+    /// let header = Header::parse_slices(&slices).unwrap();
+    ///
+    /// assert_eq!(header, Header::try_from(bytes.as_slice()).unwrap());
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn parse_slices<S: core::ops::Deref<Target = [u8]>>(
+        slices: &'a [S],
+    ) -> Result<Self, ParseError> {
+        Header::parse_slices_with(slices, &ParseOptions::new())
+    }
+
+    /// Parses a header from a list of slices with the given `ParseOptions`.
+    /// See `parse_slices` for details.
+    #[cfg(feature = "alloc")]
+    pub fn parse_slices_with<S: core::ops::Deref<Target = [u8]>>(
+        slices: &'a [S],
+        options: &ParseOptions,
+    ) -> Result<Self, ParseError> {
+        let first = scatter::first(slices);
+        let header = Header::parse_with(first, options);
+        let total_length = scatter::total_len(slices);
+
+        if header.is_complete() || first.len() == total_length {
+            return header;
+        }
+
+        let fixed = scatter::coalesce(slices, MINIMUM_LENGTH);
+        let length = match Header::parse_with(fixed.as_slice(), options) {
+            Err(ParseError::Partial(_, length)) if total_length >= MINIMUM_LENGTH + length => {
+                MINIMUM_LENGTH + length
+            }
+            Ok(header) => return Ok(header.to_owned()),
+            Err(ParseError::Partial(_, length)) => {
+                return Err(ParseError::Partial(total_length - MINIMUM_LENGTH, length))
+            }
+            Err(error) => return Err(error),
+        };
+        let bytes = scatter::coalesce(slices, length);
+
+        Header::parse_with(bytes.as_slice(), options).map(|header| header.to_owned())
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use model::{Type, TypeLengthValue};
+    use std::borrow::Cow;
 
     #[test]
     fn no_tlvs() {
@@ -746,4 +977,332 @@ mod tests {
             ParseError::Incomplete(PROTOCOL_PREFIX.len())
         );
     }
+
+    #[test]
+    fn parse_slices() {
+        let mut input = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv6::new([1; 8], [2; 8], 80, 443),
+        )
+        .write_tlv(Type::NoOp, [42].as_slice())
+        .unwrap()
+        .build()
+        .unwrap();
+        let length = input.len();
+        input.extend(b"Hello");
+        let expected = Header::try_from(input.as_slice()).unwrap();
+
+        for split in 0..input.len() {
+            let (left, right) = input.split_at(split);
+            let slices = [std::io::IoSlice::new(left), std::io::IoSlice::new(right)];
+            let header = Header::parse_slices(&slices).unwrap();
+
+            assert_eq!(header, expected);
+            assert_eq!(
+                matches!(header.header, Cow::Borrowed(_)),
+                split == 0 || split >= length
+            );
+        }
+    }
+
+    #[test]
+    fn parse_slices_errors() {
+        let input = [
+            0x0D, 0x0A, 0x0D, 0x0A, 0x00, 0x0D, 0x0A, 0x51, 0x55, 0x49, 0x54, 0x0A,
+        ];
+
+        assert_eq!(
+            Header::parse_slices(&[&input[..5], &input[5..]]),
+            Err(ParseError::Incomplete(12))
+        );
+        assert_eq!(
+            Header::parse_slices(&[&input[..5], &input[5..], &[0x21, 0x11, 0, 12, 127]]),
+            Err(ParseError::Partial(1, 12))
+        );
+        assert_eq!(
+            Header::parse_slices(&[&input[..5], &input[5..], &[0x21, 0x41, 0, 0]]),
+            Err(ParseError::AddressFamily(0x40))
+        );
+        assert_eq!(
+            Header::parse_slices(&[b"\r\n".as_slice(), b"\r\n\x01"]),
+            Err(ParseError::Prefix)
+        );
+    }
+
+    #[test]
+    fn parse_with_options() {
+        let header = Builder::with_addresses(
+            Version::Two | Command::Local,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [127, 0, 0, 2], 80, 443),
+        )
+        .write_tlv(Type::NoOp, [0; 5].as_slice())
+        .unwrap()
+        .build()
+        .unwrap();
+        let options = ParseOptions::new();
+
+        assert!(Header::parse_with(&header, &options).is_ok());
+        assert_eq!(
+            Header::parse_with(&header, &options.v2(false)),
+            Err(ParseError::Disabled)
+        );
+        assert_eq!(
+            Header::parse_with(&header[..MINIMUM_LENGTH], &options.allow_local(false)),
+            Err(ParseError::LocalCommand)
+        );
+        assert_eq!(
+            Header::parse_with(&header[..MINIMUM_LENGTH], &options.max_v2_payload(19)),
+            Err(ParseError::PayloadTooLong(20, 19))
+        );
+        assert!(Header::parse_with(&header, &options.max_v2_payload(20)).is_ok());
+        assert_eq!(
+            Header::parse_with(&header[..MINIMUM_LENGTH], &options.tlvs(TlvPolicy::Forbid)),
+            Err(ParseError::UnexpectedTLVs(8))
+        );
+        assert!(Header::parse_with(&header, &options.tlvs(TlvPolicy::Require)).is_ok());
+    }
+
+    #[test]
+    fn parse_with_required_tlvs() {
+        let options = ParseOptions::new().tlvs(TlvPolicy::Require);
+        let header = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [127, 0, 0, 2], 80, 443),
+        )
+        .build()
+        .unwrap();
+        let unspecified = Builder::new(
+            Version::Two | Command::Proxy,
+            AddressFamily::Unspecified | Protocol::Unspecified,
+        )
+        .write_payload([1u8, 2, 3].as_slice())
+        .unwrap()
+        .build()
+        .unwrap();
+
+        assert_eq!(
+            Header::parse_with(&header, &options),
+            Err(ParseError::MissingTLVs)
+        );
+        assert_eq!(
+            Header::parse_with(&unspecified, &options),
+            Err(ParseError::MissingTLVs)
+        );
+        assert!(Header::parse_with(&unspecified, &options.tlvs(TlvPolicy::Forbid)).is_ok());
+    }
+
+    #[test]
+    fn parse_with_validated_tlvs() {
+        let options = ParseOptions::new().validate_tlvs(true);
+        let addresses = IPv4::new([127, 0, 0, 1], [127, 0, 0, 2], 80, 443);
+        let header = |tlvs: &[u8]| {
+            Builder::with_addresses(Version::Two | Command::Proxy, Protocol::Stream, addresses)
+                .write_payload(tlvs)
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+
+        let valid = header(&[0x02, 0, 1, b'a', 0x20, 0, 8, 1, 0, 0, 0, 0, 0x21, 0, 0]);
+        let truncated = header(&[0x02, 0, 1, b'a', 0x04]);
+        let overrun = header(&[0x02, 0, 1, b'a', 0x04, 0, 2, 0]);
+        let short_ssl = header(&[0x20, 0, 2, 1, 0]);
+        let nested = header(&[0x20, 0, 8, 1, 0, 0, 0, 0, 0x21, 0, 1]);
+
+        assert!(Header::parse_with(&valid, &options).is_ok());
+        assert!(Header::parse_with(&truncated, &ParseOptions::new()).is_ok());
+        assert_eq!(
+            Header::parse_with(&truncated, &options),
+            Err(ParseError::MalformedTLV {
+                offset: 32,
+                required: 3,
+                available: 1
+            })
+        );
+        assert_eq!(
+            Header::parse_with(&overrun, &options),
+            Err(ParseError::MalformedTLV {
+                offset: 32,
+                required: 5,
+                available: 4
+            })
+        );
+        assert_eq!(
+            Header::parse_with(&short_ssl, &options),
+            Err(ParseError::MalformedTLV {
+                offset: 28,
+                required: 8,
+                available: 5
+            })
+        );
+        assert_eq!(
+            Header::parse_with(&nested, &options),
+            Err(ParseError::MalformedTLV {
+                offset: 36,
+                required: 4,
+                available: 3
+            })
+        );
+        assert_eq!(
+            crate::HeaderResult::parse_with(&nested, &options),
+            crate::HeaderResult::V2(Err(ParseError::MalformedTLV {
+                offset: 36,
+                required: 4,
+                available: 3
+            }))
+        );
+    }
+
+    #[test]
+    fn tlvs_lenient() {
+        let header = |tlvs: &[u8]| {
+            Builder::with_addresses(
+                Version::Two | Command::Proxy,
+                Protocol::Stream,
+                IPv4::new([127, 0, 0, 1], [127, 0, 0, 2], 80, 443),
+            )
+            .write_payload(tlvs)
+            .unwrap()
+            .build()
+            .unwrap()
+        };
+        let authority = TypeLengthValue::new(Type::Authority, b"a");
+
+        let valid = header(&[0x02, 0, 1, b'a', 0x04, 0, 0]);
+        let parsed = Header::try_from(valid.as_slice()).unwrap();
+
+        assert_eq!(
+            parsed.tlvs_lenient().collect::<Vec<_>>(),
+            vec![
+                Ok(authority.clone()),
+                Ok(TypeLengthValue::new(Type::NoOp, &[]))
+            ]
+        );
+
+        let trailing = header(&[0x02, 0, 1, b'a', 0xFF]);
+        let parsed = Header::try_from(trailing.as_slice()).unwrap();
+
+        assert_eq!(
+            parsed.tlvs_lenient().collect::<Vec<_>>(),
+            vec![
+                Ok(authority.clone()),
+                Err(MalformedRegion {
+                    offset: 32,
+                    length: 1,
+                    kind: Some(0xFF),
+                    declared: None
+                })
+            ]
+        );
+
+        let overrun = header(&[0x02, 0, 1, b'a', 0x05, 0, 9, 1, 2]);
+        let parsed = Header::try_from(overrun.as_slice()).unwrap();
+
+        assert_eq!(
+            parsed.tlvs_lenient().collect::<Vec<_>>(),
+            vec![
+                Ok(authority.clone()),
+                Err(MalformedRegion {
+                    offset: 32,
+                    length: 5,
+                    kind: Some(0x05),
+                    declared: Some(9)
+                })
+            ]
+        );
+
+        let invalid = header(&[
+            0x20, 0, 6, 1, 0, 0, 0, 0, 0x22, 0x02, 0, 1, b'a', 0x03, 0, 2, 0, 0, 0x04, 0, 0,
+        ]);
+        let parsed = Header::try_from(invalid.as_slice()).unwrap();
+
+        assert_eq!(
+            parsed.tlvs_lenient().collect::<Vec<_>>(),
+            vec![
+                Err(MalformedRegion {
+                    offset: 28,
+                    length: 9,
+                    kind: Some(0x20),
+                    declared: Some(6)
+                }),
+                Ok(authority),
+                Err(MalformedRegion {
+                    offset: 41,
+                    length: 5,
+                    kind: Some(0x03),
+                    declared: Some(2)
+                }),
+                Ok(TypeLengthValue::new(Type::NoOp, &[])),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_with_tlv_kinds() {
+        let header = |kind: u8| {
+            Builder::with_addresses(
+                Version::Two | Command::Proxy,
+                Protocol::Stream,
+                IPv4::new([127, 0, 0, 1], [127, 0, 0, 2], 80, 443),
+            )
+            .write_tlv(Type::Authority, b"a")
+            .unwrap()
+            .write_tlv(Type::SSL, &[1, 0, 0, 0, 0, kind, 0, 0])
+            .unwrap()
+            .build()
+            .unwrap()
+        };
+        let options = ParseOptions::new()
+            .allow_unassigned_tlvs(false)
+            .allow_future_tlvs(false);
+
+        for kind in [0x21, 0xE0, 0xF7] {
+            assert!(Header::parse_with(&header(kind), &options).is_ok());
+        }
+
+        assert!(Header::parse_with(&header(0x06), &ParseOptions::new()).is_ok());
+        assert_eq!(
+            Header::parse_with(&header(0x06), &options),
+            Err(ParseError::RejectedTLV {
+                offset: 40,
+                kind: 0x06
+            })
+        );
+        assert!(Header::parse_with(&header(0x06), &options.allow_unassigned_tlvs(true)).is_ok());
+        assert_eq!(
+            Header::parse_with(&header(0xF8), &options),
+            Err(ParseError::RejectedTLV {
+                offset: 40,
+                kind: 0xF8
+            })
+        );
+    }
+
+    #[test]
+    fn classify_tlv_kinds() {
+        for kind in 0..=u8::MAX {
+            let classified = TlvKind::classify(kind);
+
+            assert_eq!(u8::from(classified), kind);
+
+            match Type::try_from(kind) {
+                Ok(known) => {
+                    assert_eq!(u8::from(known), kind);
+                    assert_eq!(classified, TlvKind::Standard(known));
+                }
+                Err(error) => {
+                    assert_eq!(error, ParseError::UnknownType(kind));
+                    assert_ne!(classified, TlvKind::Standard(Type::NoOp));
+                }
+            }
+        }
+
+        assert_eq!(
+            TypeLengthValue::new(0xEF, &[]).classify(),
+            TlvKind::Custom(0xEF)
+        );
+    }
 }
@@ -1,9 +1,13 @@
 use crate::ip::{IPv4, IPv6};
 use crate::v2::error::ParseError;
-use std::borrow::Cow;
-use std::fmt;
-use std::net::SocketAddr;
-use std::ops::BitOr;
+use crate::v2::{validate_tlvs, NetworkNamespace, UniqueId, SSL_FIXED_LENGTH};
+use crate::MaybeOwned;
+use crate::ParseOptions;
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+use core::fmt;
+use core::net::SocketAddr;
+use core::ops::BitOr;
 
 /// The prefix of the PROXY protocol header.
 pub const PROTOCOL_PREFIX: &[u8] = b"\r\n\r\n\0\r\nQUIT\n";
@@ -44,7 +48,7 @@ const UNIX_ADDRESSES_BYTES: usize = 216;
 /// ```
 #[derive(Clone, Debug, PartialEq)]
 pub struct Header<'a> {
-    pub header: Cow<'a, [u8]>,
+    pub header: MaybeOwned<'a, [u8]>,
     pub version: Version,
     pub command: Command,
     pub protocol: Protocol,
@@ -53,12 +57,14 @@ pub struct Header<'a> {
 
 /// The supported `Version`s for binary headers.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Version {
     Two = 0x20,
 }
 
 /// The supported `Command`s for a PROXY protocol header.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Command {
     Local = 0,
     Proxy,
@@ -66,6 +72,7 @@ pub enum Command {
 
 /// The supported `AddressFamily` for a PROXY protocol header.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AddressFamily {
     Unspecified = 0x00,
     IPv4 = 0x10,
@@ -75,6 +82,7 @@ pub enum AddressFamily {
 
 /// The supported `Protocol`s for a PROXY protocol header.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Protocol {
     Unspecified = 0,
     Stream,
@@ -93,6 +101,7 @@ pub enum Protocol {
 /// assert_eq!(addresses.address_family(), AddressFamily::IPv4);
 /// ```
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Addresses {
     Unspecified,
     IPv4(IPv4),
@@ -112,17 +121,70 @@ pub struct Unix {
 pub struct TypeLengthValues<'a> {
     bytes: &'a [u8],
     offset: usize,
+    skip_noop: bool,
+}
+
+/// An `Iterator` of `TypeLengthValue`s that reports malformed bytes instead of failing.
+/// Every valid `TypeLengthValue` is returned, and every invalid one is reported as a `MalformedRegion`.
+/// A `TypeLengthValue` whose value is invalid for its type (i.e. malformed nested `SSL` TLVs, a `CRC32C` that is not 4 bytes,
+/// a unique ID that is too long or an invalid network namespace) is skipped and iteration continues after it.
+/// When the length of a `TypeLengthValue` cannot be trusted, the region extends to the end of the header and iteration ends.
+///
+/// ## Examples
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use ppp::v2::{Builder, Command, Header, IPv4, MalformedRegion, Protocol, Type, Version};
+///
+/// let bytes = Builder::with_addresses(
+///     Version::Two | Command::Proxy,
+///     Protocol::Stream,
+///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+/// )
+/// .write_tlv(Type::CRC32C, &[0; 2])
+/// .unwrap()
+/// .write_tlv(Type::Authority, b"example.com")
+/// .unwrap()
+/// .write_payload([0x05u8, 0].as_slice())
+/// .unwrap()
+/// .build()
+/// .unwrap();
+/// let header = Header::try_from(bytes.as_slice()).unwrap();
+/// let tlvs: Vec<_> = header.tlvs_lenient().collect();
+///
+/// assert_eq!(tlvs[0], Err(MalformedRegion { offset: 28, length: 5, kind: Some(0x03), declared: Some(2) }));
+/// assert_eq!(tlvs[1], Ok((Type::Authority, b"example.com".as_slice()).into()));
+/// assert_eq!(tlvs[2], Err(MalformedRegion { offset: 47, length: 2, kind: Some(0x05), declared: None }));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LenientTypeLengthValues<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    base: usize,
+}
+
+/// Bytes of a header that do not form a valid `TypeLengthValue`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MalformedRegion {
+    /// The offset of the region from the start of the header.
+    pub offset: usize,
+    /// The length of the region in bytes.
+    pub length: usize,
+    /// The type of the malformed `TypeLengthValue`, if at least one byte remains.
+    pub kind: Option<u8>,
+    /// The declared length of the malformed `TypeLengthValue`'s value, if the length bytes are present.
+    pub declared: Option<u16>,
 }
 
 /// A Type-Length-Value payload.
 #[derive(Clone, Debug, PartialEq)]
 pub struct TypeLengthValue<'a> {
     pub kind: u8,
-    pub value: Cow<'a, [u8]>,
+    pub value: MaybeOwned<'a, [u8]>,
 }
 
 /// Supported types for `TypeLengthValue` payloads.
 #[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     ALPN = 0x01,
     Authority,
@@ -138,6 +200,32 @@ pub enum Type {
     NetworkNamespace = 0x30,
 }
 
+/// The range of the specification a `TypeLengthValue`'s type belongs to.
+///
+/// ## Examples
+/// ```rust
+/// use ppp::v2::{TlvKind, Type};
+///
+/// assert_eq!(TlvKind::classify(0x02), TlvKind::Standard(Type::Authority));
+/// assert_eq!(TlvKind::classify(0xE5), TlvKind::Custom(0xE5));
+/// assert_eq!(TlvKind::classify(0xF0), TlvKind::Experimental(0xF0));
+/// assert_eq!(TlvKind::classify(0xFF), TlvKind::Future(0xFF));
+/// assert_eq!(TlvKind::classify(0x06), TlvKind::Unassigned(0x06));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TlvKind {
+    /// A type defined by the specification.
+    Standard(Type),
+    /// A type reserved for application-specific data (`0xE0` to `0xEF`).
+    Custom(u8),
+    /// A type reserved for temporary experimental use (`0xF0` to `0xF7`).
+    Experimental(u8),
+    /// A type reserved for future use by the specification (`0xF8` to `0xFF`).
+    Future(u8),
+    /// A type in the specification's ranges that is not yet assigned.
+    Unassigned(u8),
+}
+
 impl<'a> fmt::Display for Header<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -153,6 +241,7 @@ impl<'a> fmt::Display for Header<'a> {
 
 impl<'a> Header<'a> {
     /// Creates an owned clone of this [`Header`].
+    #[cfg(feature = "alloc")]
     pub fn to_owned(&self) -> Header<'static> {
         Header {
             header: Cow::Owned(self.header.to_vec()),
@@ -183,12 +272,12 @@ impl<'a> Header<'a> {
         self.addresses.address_family()
     }
 
-    /// The length in bytes of the address portion of the payload.
-    fn address_bytes_end(&self) -> usize {
+    /// The offset of the end of the address portion of the payload.
+    pub(crate) fn address_bytes_end(&self) -> usize {
         let length = self.length();
         let address_bytes = self.address_family().byte_length().unwrap_or(length);
 
-        MINIMUM_LENGTH + std::cmp::min(address_bytes, length)
+        MINIMUM_LENGTH + core::cmp::min(address_bytes, length)
     }
 
     /// The bytes of the address portion of the payload.
@@ -203,13 +292,80 @@ impl<'a> Header<'a> {
 
     /// An `Iterator` of `TypeLengthValue`s.
     pub fn tlvs(&self) -> TypeLengthValues<'_> {
-        TypeLengthValues {
-            bytes: self.tlv_bytes().into(),
-            offset: 0,
+        TypeLengthValues::from(self.tlv_bytes())
+    }
+
+    /// An `Iterator` of `TypeLengthValue`s that reports malformed bytes instead of failing.
+    /// See `LenientTypeLengthValues` for details.
+    pub fn tlvs_lenient(&self) -> LenientTypeLengthValues<'_> {
+        LenientTypeLengthValues::new(self.tlv_bytes(), self.address_bytes_end())
+    }
+
+    /// The first `TypeLengthValue` of the given type.
+    /// Returns an error if a malformed `TypeLengthValue` is found before one of the given type.
+    ///
+    /// ## Examples
+    #[cfg_attr(feature = "std", doc = "```rust")]
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
+    /// use ppp::v2::{Builder, Command, Header, IPv4, Protocol, Type, Version};
+    ///
+    /// let bytes = Builder::with_addresses(
+    ///     Version::Two | Command::Proxy,
+    ///     Protocol::Stream,
+    ///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+    /// )
+    /// .write_tlv(Type::Authority, b"example.com")
+    /// .unwrap()
+    /// .write_tlv(Type::NoOp, &[0])
+    /// .unwrap()
+    /// .write_tlv(Type::NoOp, &[1])
+    /// .unwrap()
+    /// .build()
+    /// .unwrap();
+    /// let header = Header::try_from(bytes.as_slice()).unwrap();
+    ///
+    /// assert_eq!(header.tlv(Type::Authority), Some(Ok((Type::Authority, b"example.com".as_slice()).into())));
+    /// assert_eq!(header.tlv(Type::UniqueId), None);
+    /// assert_eq!(header.tlvs_of(Type::NoOp).count(), 2);
+    /// ```
+    pub fn tlv<T: Into<u8>>(&self, kind: T) -> Option<Result<TypeLengthValue<'_>, ParseError>> {
+        self.tlvs_of(kind).next()
+    }
+
+    /// An `Iterator` of the `TypeLengthValue`s of the given type, in the order they appear in the header.
+    /// A malformed `TypeLengthValue` ends the iteration with an error.
+    pub fn tlvs_of<T: Into<u8>>(
+        &self,
+        kind: T,
+    ) -> impl Iterator<Item = Result<TypeLengthValue<'_>, ParseError>> {
+        let kind = kind.into();
+
+        self.tlvs().filter(move |tlv| match tlv {
+            Ok(tlv) => tlv.kind == kind,
+            Err(_) => true,
+        })
+    }
+
+    /// The value of the first `TypeLengthValue` of the given type, borrowed from the header.
+    pub(crate) fn tlv_value<T: Into<u8>>(&self, kind: T) -> Option<Result<&[u8], ParseError>> {
+        let kind = kind.into();
+        let mut tlvs = self.tlvs();
+
+        loop {
+            let start = tlvs.offset;
+
+            match tlvs.next()? {
+                Ok(tlv) if tlv.kind == kind => {
+                    return Some(Ok(&tlvs.bytes[start + MINIMUM_TLV_LENGTH..tlvs.offset]))
+                }
+                Ok(_) => continue,
+                Err(error) => return Some(Err(error)),
+            }
         }
     }
 
     /// The underlying byte slice this `Header` is built on.
+    #[cfg_attr(not(feature = "alloc"), allow(clippy::useless_asref))]
     pub fn as_bytes(&self) -> &[u8] {
         self.header.as_ref()
     }
@@ -217,16 +373,51 @@ impl<'a> Header<'a> {
 
 impl<'a> TypeLengthValues<'a> {
     /// The underlying byte slice of the `TypeLengthValue`s portion of the `Header` payload.
-    pub fn as_bytes(&self) -> &[u8] {
-        self.bytes.as_ref()
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The offset in `as_bytes` of the next `TypeLengthValue` to be parsed.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Sets whether `NoOp` TLVs, which are typically padding, are skipped by the iterator.
+    ///
+    /// ## Examples
+    #[cfg_attr(feature = "std", doc = "```rust")]
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
+    /// use ppp::v2::{Builder, Command, Header, IPv4, Protocol, Type, Version};
+    ///
+    /// let bytes = Builder::with_addresses(
+    ///     Version::Two | Command::Proxy,
+    ///     Protocol::Stream,
+    ///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+    /// )
+    /// .write_tlv(Type::Authority, b"example.com")
+    /// .unwrap()
+    /// .pad_to(8)
+    /// .unwrap()
+    /// .build()
+    /// .unwrap();
+    /// let header = Header::try_from(bytes.as_slice()).unwrap();
+    ///
+    /// assert_eq!(header.tlvs().count(), 2);
+    /// assert_eq!(header.tlvs().skip_noop(true).count(), 1);
+    /// ```
+    pub fn skip_noop(mut self, skip: bool) -> Self {
+        self.skip_noop = skip;
+        self
     }
 }
 
 impl<'a> From<&'a [u8]> for TypeLengthValues<'a> {
     fn from(bytes: &'a [u8]) -> Self {
         TypeLengthValues {
-            bytes: bytes.into(),
+            bytes,
             offset: 0,
+            skip_noop: false,
         }
     }
 }
@@ -235,32 +426,107 @@ impl<'a> Iterator for TypeLengthValues<'a> {
     type Item = Result<TypeLengthValue<'a>, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.offset >= self.bytes.len() {
-            return None;
-        }
+        loop {
+            if self.offset >= self.bytes.len() {
+                return None;
+            }
 
-        let remaining = &self.bytes[self.offset..];
+            let remaining = &self.bytes[self.offset..];
 
-        if remaining.len() < MINIMUM_TLV_LENGTH {
-            self.offset = self.bytes.len();
-            return Some(Err(ParseError::Leftovers(self.bytes.len())));
-        }
+            if remaining.len() < MINIMUM_TLV_LENGTH {
+                self.offset = self.bytes.len();
+                return Some(Err(ParseError::Leftovers(self.bytes.len())));
+            }
+
+            let tlv_type = remaining[0];
+            let length = u16::from_be_bytes([remaining[1], remaining[2]]);
+            let tlv_length = MINIMUM_TLV_LENGTH + length as usize;
+
+            if remaining.len() < tlv_length {
+                self.offset = self.bytes.len();
+                return Some(Err(ParseError::InvalidTLV(tlv_type, length)));
+            }
+
+            self.offset += tlv_length;
 
-        let tlv_type = remaining[0];
-        let length = u16::from_be_bytes([remaining[1], remaining[2]]);
-        let tlv_length = MINIMUM_TLV_LENGTH + length as usize;
+            if self.skip_noop && tlv_type == Type::NoOp as u8 {
+                continue;
+            }
 
-        if remaining.len() < tlv_length {
-            self.offset = self.bytes.len();
-            return Some(Err(ParseError::InvalidTLV(tlv_type, length)));
+            return Some(Ok(TypeLengthValue {
+                kind: tlv_type,
+                value: crate::borrowed(&remaining[MINIMUM_TLV_LENGTH..tlv_length]),
+            }));
         }
+    }
+}
+
+impl<'a> LenientTypeLengthValues<'a> {
+    /// Creates an `Iterator` over the `TypeLengthValue` bytes, which start at the given offset in the header.
+    pub(crate) fn new(bytes: &'a [u8], base: usize) -> Self {
+        LenientTypeLengthValues {
+            bytes,
+            offset: 0,
+            base,
+        }
+    }
+}
 
-        self.offset += tlv_length;
+impl<'a> Iterator for LenientTypeLengthValues<'a> {
+    type Item = Result<TypeLengthValue<'a>, MalformedRegion>;
 
-        Some(Ok(TypeLengthValue {
-            kind: tlv_type,
-            value: Cow::Borrowed(&remaining[MINIMUM_TLV_LENGTH..tlv_length]),
-        }))
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.bytes.get(self.offset..).filter(|r| !r.is_empty())?;
+        let mut region = MalformedRegion {
+            offset: self.base + self.offset,
+            length: remaining.len(),
+            kind: remaining.first().copied(),
+            declared: remaining
+                .get(1..MINIMUM_TLV_LENGTH)
+                .map(|length| u16::from_be_bytes([length[0], length[1]])),
+        };
+        let tlv_length = region
+            .declared
+            .map(|length| MINIMUM_TLV_LENGTH + length as usize)
+            .filter(|length| *length <= remaining.len());
+
+        match (region.kind, tlv_length) {
+            (Some(kind), Some(tlv_length)) => {
+                let value = &remaining[MINIMUM_TLV_LENGTH..tlv_length];
+
+                self.offset += tlv_length;
+
+                if !valid_value(kind, value) {
+                    region.length = tlv_length;
+
+                    return Some(Err(region));
+                }
+
+                Some(Ok(TypeLengthValue {
+                    kind,
+                    value: crate::borrowed(value),
+                }))
+            }
+            _ => {
+                self.offset = self.bytes.len();
+
+                Some(Err(region))
+            }
+        }
+    }
+}
+
+/// Tests whether the value of a `TypeLengthValue` is valid for its type.
+fn valid_value(kind: u8, value: &[u8]) -> bool {
+    match Type::try_from(kind) {
+        Ok(Type::SSL) => {
+            value.len() >= SSL_FIXED_LENGTH
+                && validate_tlvs(value, SSL_FIXED_LENGTH, &ParseOptions::new()).is_ok()
+        }
+        Ok(Type::CRC32C) => value.len() == 4,
+        Ok(Type::UniqueId) => UniqueId::new(value).is_ok(),
+        Ok(Type::NetworkNamespace) => NetworkNamespace::try_from(value).is_ok(),
+        _ => true,
     }
 }
 
@@ -402,13 +668,14 @@ impl<'a, T: Into<u8>> From<(T, &'a [u8])> for TypeLengthValue<'a> {
     fn from((kind, value): (T, &'a [u8])) -> Self {
         TypeLengthValue {
             kind: kind.into(),
-            value: value.into(),
+            value: crate::borrowed(value),
         }
     }
 }
 
 impl<'a> TypeLengthValue<'a> {
     /// Creates an owned clone of this [`TypeLengthValue`].
+    #[cfg(feature = "alloc")]
     pub fn to_owned(&self) -> TypeLengthValue<'static> {
         TypeLengthValue {
             kind: self.kind,
@@ -421,10 +688,15 @@ impl<'a> TypeLengthValue<'a> {
     pub fn new<T: Into<u8>>(kind: T, value: &'a [u8]) -> Self {
         TypeLengthValue {
             kind: kind.into(),
-            value: value.into(),
+            value: crate::borrowed(value),
         }
     }
 
+    /// The `TlvKind` of this `TypeLengthValue`'s type.
+    pub fn classify(&self) -> TlvKind {
+        TlvKind::classify(self.kind)
+    }
+
     /// The length in bytes of this `TypeLengthValue`'s value.
     pub fn len(&self) -> usize {
         self.value.len()
@@ -441,3 +713,59 @@ impl From<Type> for u8 {
         kind as u8
     }
 }
+
+impl TryFrom<u8> for Type {
+    type Error = ParseError;
+
+    fn try_from(kind: u8) -> Result<Self, Self::Error> {
+        let kind = match kind {
+            0x01 => Type::ALPN,
+            0x02 => Type::Authority,
+            0x03 => Type::CRC32C,
+            0x04 => Type::NoOp,
+            0x05 => Type::UniqueId,
+            0x20 => Type::SSL,
+            0x21 => Type::SSLVersion,
+            0x22 => Type::SSLCommonName,
+            0x23 => Type::SSLCipher,
+            0x24 => Type::SSLSignatureAlgorithm,
+            0x25 => Type::SSLKeyAlgorithm,
+            0x30 => Type::NetworkNamespace,
+            kind => return Err(ParseError::UnknownType(kind)),
+        };
+
+        Ok(kind)
+    }
+}
+
+impl TlvKind {
+    /// Classifies the type of a `TypeLengthValue` by the range the specification assigns it to.
+    pub fn classify(kind: u8) -> Self {
+        match kind {
+            0xE0..=0xEF => TlvKind::Custom(kind),
+            0xF0..=0xF7 => TlvKind::Experimental(kind),
+            0xF8..=0xFF => TlvKind::Future(kind),
+            _ => Type::try_from(kind)
+                .map(TlvKind::Standard)
+                .unwrap_or(TlvKind::Unassigned(kind)),
+        }
+    }
+}
+
+impl From<u8> for TlvKind {
+    fn from(kind: u8) -> Self {
+        TlvKind::classify(kind)
+    }
+}
+
+impl From<TlvKind> for u8 {
+    fn from(kind: TlvKind) -> Self {
+        match kind {
+            TlvKind::Standard(kind) => kind.into(),
+            TlvKind::Custom(kind)
+            | TlvKind::Experimental(kind)
+            | TlvKind::Future(kind)
+            | TlvKind::Unassigned(kind) => kind,
+        }
+    }
+}
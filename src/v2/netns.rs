@@ -0,0 +1,187 @@
+//! Typed access to the `NetworkNamespace` TLV.
+
+use crate::v2::{Header, ParseError, Type};
+use core::fmt;
+
+/// The maximum length in bytes of a network namespace name, which is a file name on Linux.
+pub const MAX_NETWORK_NAMESPACE_LENGTH: usize = 255;
+
+/// The name of a network namespace from a `NetworkNamespace` TLV.
+/// Names are non-empty UTF-8 of at most `MAX_NETWORK_NAMESPACE_LENGTH` bytes, without any `/` or NUL characters,
+/// and are neither `.` nor `..`, so they are always a single path component.
+///
+/// ## Examples
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use ppp::v2::{Builder, Command, Header, IPv4, NetworkNamespace, Protocol, Version};
+///
+/// let bytes = Builder::with_addresses(
+///     Version::Two | Command::Proxy,
+///     Protocol::Stream,
+///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+/// )
+/// .write_payload(NetworkNamespace::new("tenant-a").unwrap())
+/// .unwrap()
+/// .build()
+/// .unwrap();
+/// let header = Header::try_from(bytes.as_slice()).unwrap();
+///
+/// assert_eq!(header.network_namespace().unwrap().unwrap().as_str(), "tenant-a");
+/// assert!(NetworkNamespace::new("../etc").is_err());
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NetworkNamespace<'a>(&'a str);
+
+impl<'a> NetworkNamespace<'a> {
+    /// Validates the name of a network namespace.
+    pub fn new(name: &'a str) -> Result<Self, ParseError> {
+        let valid = !name.is_empty()
+            && name.len() <= MAX_NETWORK_NAMESPACE_LENGTH
+            && name != "."
+            && name != ".."
+            && !name.contains(['/', '\0']);
+
+        if valid {
+            Ok(NetworkNamespace(name))
+        } else {
+            Err(ParseError::InvalidNetworkNamespace)
+        }
+    }
+
+    /// The name of the network namespace.
+    pub fn as_str(&self) -> &'a str {
+        self.0
+    }
+
+    /// The path of the namespace's bind mount created by `ip netns`, i.e. `/var/run/netns/<name>`.
+    #[cfg(all(feature = "std", target_os = "linux"))]
+    pub fn path(&self) -> std::path::PathBuf {
+        std::path::Path::new("/var/run/netns").join(self.0)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for NetworkNamespace<'a> {
+    type Error = ParseError;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let name = core::str::from_utf8(value).map_err(ParseError::NetworkNamespaceUtf8)?;
+
+        NetworkNamespace::new(name)
+    }
+}
+
+impl<'a> AsRef<str> for NetworkNamespace<'a> {
+    fn as_ref(&self) -> &str {
+        self.0
+    }
+}
+
+impl<'a> fmt::Display for NetworkNamespace<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl<'a> Header<'a> {
+    /// The validated name from the first `NetworkNamespace` TLV.
+    /// Returns an error if the TLVs are malformed or the name is not a valid `NetworkNamespace`.
+    pub fn network_namespace(&self) -> Option<Result<NetworkNamespace<'_>, ParseError>> {
+        self.tlv_value(Type::NetworkNamespace)
+            .map(|value| value.and_then(NetworkNamespace::try_from))
+    }
+}
+
+#[cfg(feature = "std")]
+mod write {
+    use super::NetworkNamespace;
+    use crate::v2::{Type, TypeLengthValue, WriteToHeader, Writer};
+    use std::io;
+
+    impl<'a> WriteToHeader for NetworkNamespace<'a> {
+        /// Writes this name as a `NetworkNamespace` TLV.
+        fn write_to(&self, writer: &mut Writer) -> io::Result<usize> {
+            TypeLengthValue::new(Type::NetworkNamespace, self.0.as_bytes()).write_to(writer)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::v2::{Builder, Command, IPv4, Protocol, Version};
+
+    fn header(value: &[u8]) -> Vec<u8> {
+        Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .write_tlv(Type::NetworkNamespace, value)
+        .unwrap()
+        .build()
+        .unwrap()
+    }
+
+    #[test]
+    fn validate() {
+        assert!(NetworkNamespace::new("blue").is_ok());
+        assert!(NetworkNamespace::new("..blue").is_ok());
+        assert!(NetworkNamespace::new(&"a".repeat(MAX_NETWORK_NAMESPACE_LENGTH)).is_ok());
+
+        for name in ["", ".", "..", "a/b", "a\0", &"a".repeat(256)] {
+            assert_eq!(
+                NetworkNamespace::new(name),
+                Err(ParseError::InvalidNetworkNamespace),
+                "{name:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn accessor() {
+        let valid = header(b"red");
+        let invalid = header(&[0xFF, 0xFE]);
+        let traversal = header(b"../../etc");
+        let missing = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .build()
+        .unwrap();
+
+        assert_eq!(
+            Header::try_from(valid.as_slice())
+                .unwrap()
+                .network_namespace(),
+            Some(Ok(NetworkNamespace("red")))
+        );
+        assert!(matches!(
+            Header::try_from(invalid.as_slice())
+                .unwrap()
+                .network_namespace(),
+            Some(Err(ParseError::NetworkNamespaceUtf8(_)))
+        ));
+        assert_eq!(
+            Header::try_from(traversal.as_slice())
+                .unwrap()
+                .network_namespace(),
+            Some(Err(ParseError::InvalidNetworkNamespace))
+        );
+        assert_eq!(
+            Header::try_from(missing.as_slice())
+                .unwrap()
+                .network_namespace(),
+            None
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn path() {
+        assert_eq!(
+            NetworkNamespace::new("blue").unwrap().path(),
+            std::path::Path::new("/var/run/netns/blue")
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! HMAC-SHA256 signatures of PROXY protocol v2 headers, carried in a custom TLV.
+//!
+//! The value of the signature TLV is a 4-byte big-endian key ID, an 8-byte big-endian timestamp
+//! in seconds since the Unix epoch and a 32-byte HMAC-SHA256.
+//! The HMAC covers every byte of the header, with the HMAC field of the signature TLV zeroed.
+
+use crate::v2::{BuildError, Builder, Header, ParseError, SignatureError, MINIMUM_TLV_LENGTH};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The default type code of the signature TLV, from the custom range.
+pub const SIGNATURE_TLV: u8 = 0xEE;
+/// The length in bytes of the value of the signature TLV.
+pub const SIGNATURE_LENGTH: usize = KEY_ID_LENGTH + TIMESTAMP_LENGTH + MAC_LENGTH;
+
+/// The length in bytes of the key ID.
+const KEY_ID_LENGTH: usize = 4;
+/// The length in bytes of the timestamp.
+const TIMESTAMP_LENGTH: usize = 8;
+/// The length in bytes of an HMAC-SHA256.
+const MAC_LENGTH: usize = 32;
+
+/// Signs headers with a shared key.
+///
+/// ## Examples
+/// ```rust
+/// use ppp::v2::{Builder, Command, Header, IPv4, Protocol, Signer, Verifier, Version};
+///
+/// let signer = Signer::new(1, b"secret");
+/// let verifier = Verifier::new().key(1, b"secret");
+/// let bytes = Builder::with_addresses(
+///     Version::Two | Command::Proxy,
+///     Protocol::Stream,
+///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+/// )
+/// .build_signed(&signer)
+/// .unwrap();
+/// let header = Header::try_from(bytes.as_slice()).unwrap();
+///
+/// assert_eq!(verifier.verify(&header).unwrap().key_id, 1);
+/// assert!(Verifier::new().key(1, b"other").verify(&header).is_err());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Signer {
+    key_id: u32,
+    mac: Hmac<Sha256>,
+    kind: u8,
+}
+
+impl Signer {
+    /// Creates a `Signer` for the key with the given ID.
+    pub fn new(key_id: u32, key: &[u8]) -> Self {
+        Signer {
+            key_id,
+            mac: keyed(key),
+            kind: SIGNATURE_TLV,
+        }
+    }
+
+    /// Sets the type code of the signature TLV. Defaults to `SIGNATURE_TLV`.
+    pub fn kind(mut self, kind: u8) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+/// The contents of a verified signature TLV.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Signature {
+    pub key_id: u32,
+    pub timestamp: u64,
+}
+
+/// Verifies the signatures of headers with a set of shared keys.
+/// Multiple keys allow keys to be rotated without rejecting headers signed with the previous key.
+#[derive(Clone, Debug)]
+pub struct Verifier {
+    keys: Vec<(u32, Hmac<Sha256>)>,
+    max_age: Duration,
+    kind: u8,
+}
+
+impl Default for Verifier {
+    fn default() -> Self {
+        Verifier::new()
+    }
+}
+
+impl Verifier {
+    /// Creates a `Verifier` without any keys, which accepts timestamps up to 60 seconds away from the current time.
+    pub fn new() -> Self {
+        Verifier {
+            keys: Vec::new(),
+            max_age: Duration::from_secs(60),
+            kind: SIGNATURE_TLV,
+        }
+    }
+
+    /// Adds a key with the given ID, replacing any key with the same ID.
+    pub fn key(mut self, key_id: u32, key: &[u8]) -> Self {
+        self.keys.retain(|(id, _)| *id != key_id);
+        self.keys.push((key_id, keyed(key)));
+        self
+    }
+
+    /// Sets the maximum difference between the timestamp of a signature and the current time,
+    /// in either direction to tolerate clock skew.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// Sets the type code of the signature TLV. Defaults to `SIGNATURE_TLV`.
+    pub fn kind(mut self, kind: u8) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Verifies the signature of the header against the current time.
+    pub fn verify(&self, header: &Header<'_>) -> Result<Signature, SignatureError> {
+        self.verify_at(header, now())
+    }
+
+    /// Verifies the signature of the header against the given time in seconds since the Unix epoch.
+    /// Only the first signature TLV is verified.
+    pub fn verify_at(&self, header: &Header<'_>, now: u64) -> Result<Signature, SignatureError> {
+        let (start, length) = values(header, self.kind)
+            .next()
+            .ok_or(SignatureError::Missing)?
+            .map_err(SignatureError::Parse)?;
+
+        if length != SIGNATURE_LENGTH {
+            return Err(SignatureError::Malformed(length));
+        }
+
+        let bytes = header.as_bytes();
+        let value = &bytes[start..start + SIGNATURE_LENGTH];
+        let (key_id, rest) = value.split_at(KEY_ID_LENGTH);
+        let (timestamp, tag) = rest.split_at(TIMESTAMP_LENGTH);
+        let key_id = u32::from_be_bytes(key_id.try_into().unwrap());
+        let timestamp = u64::from_be_bytes(timestamp.try_into().unwrap());
+
+        let mut mac = self
+            .keys
+            .iter()
+            .find(|(id, _)| *id == key_id)
+            .map(|(_, mac)| mac.clone())
+            .ok_or(SignatureError::UnknownKey(key_id))?;
+        let mac_start = start + KEY_ID_LENGTH + TIMESTAMP_LENGTH;
+
+        mac.update(&bytes[..mac_start]);
+        mac.update(&[0; MAC_LENGTH]);
+        mac.update(&bytes[mac_start + MAC_LENGTH..]);
+        mac.verify_slice(tag)
+            .map_err(|_| SignatureError::Mismatch)?;
+
+        if now.abs_diff(timestamp) > self.max_age.as_secs() {
+            return Err(SignatureError::Stale { timestamp, now });
+        }
+
+        Ok(Signature { key_id, timestamp })
+    }
+}
+
+impl Builder {
+    /// Appends a signature TLV and builds the header, signed at the current time.
+    /// No payloads can be written afterwards.
+    /// Returns a `BuildError::DuplicateSignature` if the builder already contains a TLV of the signer's type,
+    /// since only the first one is verified, and a `BuildError::SignatureWithoutAddresses` for headers without addresses.
+    pub fn build_signed(self, signer: &Signer) -> Result<Vec<u8>, BuildError> {
+        self.build_signed_at(signer, now())
+    }
+
+    /// Appends a signature TLV and builds the header, signed at the given time in seconds since the Unix epoch.
+    pub fn build_signed_at(self, signer: &Signer, timestamp: u64) -> Result<Vec<u8>, BuildError> {
+        let mut value = [0; SIGNATURE_LENGTH];
+
+        value[..KEY_ID_LENGTH].copy_from_slice(&signer.key_id.to_be_bytes());
+        value[KEY_ID_LENGTH..KEY_ID_LENGTH + TIMESTAMP_LENGTH]
+            .copy_from_slice(&timestamp.to_be_bytes());
+
+        let mut header = self.write_tlv(signer.kind, &value)?.build()?;
+        // With canonical ordering, TLVs of a greater type code are sorted after the signature TLV.
+        let parsed = Header::try_from(header.as_slice()).map_err(BuildError::Invalid)?;
+        let signatures = values(&parsed, signer.kind)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(BuildError::Invalid)?;
+        let start = match signatures.as_slice() {
+            [] => return Err(BuildError::SignatureWithoutAddresses),
+            [(start, _)] => *start,
+            _ => return Err(BuildError::DuplicateSignature(signer.kind)),
+        };
+        let mac_start = start + KEY_ID_LENGTH + TIMESTAMP_LENGTH;
+        let mut mac = signer.mac.clone();
+
+        mac.update(&header);
+        header[mac_start..mac_start + MAC_LENGTH].copy_from_slice(&mac.finalize().into_bytes());
+
+        Ok(header)
+    }
+}
+
+/// An `Iterator` of the offsets and lengths of the values of the TLVs of the given type.
+fn values<'h>(
+    header: &'h Header<'_>,
+    kind: u8,
+) -> impl Iterator<Item = Result<(usize, usize), ParseError>> + 'h {
+    let mut offset = header.address_bytes_end();
+
+    header.tlvs().filter_map(move |tlv| {
+        let tlv = match tlv {
+            Ok(tlv) => tlv,
+            Err(error) => return Some(Err(error)),
+        };
+        let start = offset + MINIMUM_TLV_LENGTH;
+
+        offset = start + tlv.len();
+
+        (tlv.kind == kind).then_some(Ok((start, tlv.len())))
+    })
+}
+
+/// Creates an HMAC-SHA256 with the given key.
+fn keyed(key: &[u8]) -> Hmac<Sha256> {
+    Hmac::new_from_slice(key).expect("HMAC accepts keys of any length")
+}
+
+/// The current time in seconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::{AddressFamily, Command, IPv4, ParseError, Protocol, Type, Version};
+
+    fn builder() -> Builder {
+        Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .write_tlv(Type::Authority, b"example.com")
+        .unwrap()
+    }
+
+    #[test]
+    fn verify() {
+        let signer = Signer::new(7, b"secret");
+        let verifier = Verifier::new().key(1, b"old").key(7, b"secret");
+        let bytes = builder().build_signed_at(&signer, 1_000).unwrap();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            verifier.verify_at(&header, 1_030),
+            Ok(Signature {
+                key_id: 7,
+                timestamp: 1_000
+            })
+        );
+        assert_eq!(
+            verifier.verify_at(&header, 1_061),
+            Err(SignatureError::Stale {
+                timestamp: 1_000,
+                now: 1_061
+            })
+        );
+        assert_eq!(
+            verifier.verify_at(&header, 939),
+            Err(SignatureError::Stale {
+                timestamp: 1_000,
+                now: 939
+            })
+        );
+        assert_eq!(
+            Verifier::new().key(1, b"secret").verify_at(&header, 1_000),
+            Err(SignatureError::UnknownKey(7))
+        );
+    }
+
+    #[test]
+    fn tampered() {
+        let signer = Signer::new(7, b"secret");
+        let verifier = Verifier::new().key(7, b"secret");
+        let mut bytes = builder().build_signed_at(&signer, 1_000).unwrap();
+
+        // Spoof the source address.
+        bytes[16] = 10;
+
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            verifier.verify_at(&header, 1_000),
+            Err(SignatureError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn missing_or_malformed() {
+        let verifier = Verifier::new().key(7, b"secret");
+        let unsigned = builder().build().unwrap();
+        let malformed = builder()
+            .write_tlv(SIGNATURE_TLV, &[0; 4])
+            .unwrap()
+            .build()
+            .unwrap();
+        let invalid = builder()
+            .write_payload([SIGNATURE_TLV, 0].as_slice())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            verifier.verify_at(&Header::try_from(unsigned.as_slice()).unwrap(), 0),
+            Err(SignatureError::Missing)
+        );
+        assert_eq!(
+            verifier.verify_at(&Header::try_from(malformed.as_slice()).unwrap(), 0),
+            Err(SignatureError::Malformed(4))
+        );
+        assert!(matches!(
+            verifier.verify_at(&Header::try_from(invalid.as_slice()).unwrap(), 0),
+            Err(SignatureError::Parse(ParseError::Leftovers(_)))
+        ));
+    }
+
+    #[test]
+    fn canonical_order() {
+        let signer = Signer::new(7, b"secret");
+        let bytes = builder()
+            .write_tlv(0xF0, b"after")
+            .unwrap()
+            .canonical_order(true)
+            .build_signed_at(&signer, 1_000)
+            .unwrap();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+        let kinds: Vec<u8> = header.tlvs().map(|tlv| tlv.unwrap().kind).collect();
+
+        assert_eq!(kinds, vec![Type::Authority as u8, SIGNATURE_TLV, 0xF0]);
+        assert!(Verifier::new()
+            .key(7, b"secret")
+            .verify_at(&header, 1_000)
+            .is_ok());
+    }
+
+    #[test]
+    fn unsignable() {
+        let signer = Signer::new(7, b"secret");
+        let unspecified = Builder::new(
+            Version::Two | Command::Local,
+            AddressFamily::Unspecified | Protocol::Unspecified,
+        )
+        .build_signed_at(&signer, 1_000);
+        let duplicate = builder()
+            .write_tlv(SIGNATURE_TLV, &[0; SIGNATURE_LENGTH])
+            .unwrap()
+            .build_signed_at(&signer, 1_000);
+
+        assert_eq!(unspecified, Err(BuildError::SignatureWithoutAddresses));
+        assert_eq!(
+            duplicate,
+            Err(BuildError::DuplicateSignature(SIGNATURE_TLV))
+        );
+    }
+
+    #[test]
+    fn custom_kind() {
+        let signer = Signer::new(7, b"secret").kind(0xE5);
+        let bytes = builder().build_signed_at(&signer, 1_000).unwrap();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            Verifier::new().key(7, b"secret").verify_at(&header, 1_000),
+            Err(SignatureError::Missing)
+        );
+        assert!(Verifier::new()
+            .key(7, b"secret")
+            .kind(0xE5)
+            .verify_at(&header, 1_000)
+            .is_ok());
+    }
+}
@@ -0,0 +1,360 @@
+//! Prebuilt PROXY protocol v2 headers that only differ in their addresses.
+
+use crate::v2::crc32c;
+use crate::v2::fixed::write_addresses;
+use crate::v2::{
+    AddressFamily, Addresses, BuildError, Builder, Header, Type, MINIMUM_LENGTH, MINIMUM_TLV_LENGTH,
+};
+use std::net::SocketAddr;
+
+/// A PROXY protocol v2 header built once and rendered many times with different addresses.
+/// Rendering copies the prebuilt header and patches the address block in place,
+/// so the version, command, protocol and any TLVs are never rebuilt.
+/// The checksum of any `CRC32C` TLV is recomputed for the rendered addresses.
+/// Signature TLVs cannot be recomputed, so templates with a `SIGNATURE_TLV` are rejected
+/// (signatures with a custom type code are not detected and would be left stale).
+///
+/// ## Examples
+/// ```rust
+/// use ppp::v2::{Builder, Command, Header, HeaderTemplate, IPv4, Protocol, Type, Version};
+/// use std::net::SocketAddr;
+///
+/// let template = HeaderTemplate::try_from(
+///     Builder::with_addresses(
+///         Version::Two | Command::Proxy,
+///         Protocol::Stream,
+///         IPv4::new([0, 0, 0, 0], [0, 0, 0, 0], 0, 0),
+///     )
+///     .write_tlv(Type::Authority, b"example.com")
+///     .unwrap(),
+/// )
+/// .unwrap();
+///
+/// let source: SocketAddr = "127.0.0.1:80".parse().unwrap();
+/// let destination: SocketAddr = "192.168.1.1:443".parse().unwrap();
+/// let mut buffer = [0; 64];
+/// let length = template.render(source, destination, &mut buffer).unwrap();
+/// let header = Header::try_from(&buffer[..length]).unwrap();
+///
+/// assert_eq!(header.addresses, (source, destination).into());
+/// assert_eq!(header.tlv_bytes(), template.tlv_bytes());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeaderTemplate {
+    header: Vec<u8>,
+    address_family: AddressFamily,
+    tlv_offset: usize,
+    checksums: Vec<usize>,
+}
+
+impl HeaderTemplate {
+    /// The address family that addresses must match when rendering this template.
+    pub fn address_family(&self) -> AddressFamily {
+        self.address_family
+    }
+
+    /// The total length in bytes of every header rendered from this template.
+    pub fn len(&self) -> usize {
+        self.header.len()
+    }
+
+    /// Tests whether this template's underlying byte slice is empty.
+    pub fn is_empty(&self) -> bool {
+        self.header.is_empty()
+    }
+
+    /// The bytes of the `TypeLengthValue` portion shared by every rendered header.
+    pub fn tlv_bytes(&self) -> &[u8] {
+        &self.header[self.tlv_offset..]
+    }
+
+    /// The underlying bytes of this template, including the placeholder addresses.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.header.as_slice()
+    }
+
+    /// Renders the template with the given source and destination to the start of the buffer.
+    /// Returns the number of bytes written.
+    /// An error is returned if the addresses do not match the template's address family or the buffer is too small.
+    pub fn render(
+        &self,
+        source: SocketAddr,
+        destination: SocketAddr,
+        buffer: &mut [u8],
+    ) -> Result<usize, BuildError> {
+        let mismatch = [source, destination]
+            .into_iter()
+            .map(|address| match address {
+                SocketAddr::V4(_) => AddressFamily::IPv4,
+                SocketAddr::V6(_) => AddressFamily::IPv6,
+            })
+            .find(|family| *family != self.address_family);
+
+        if let Some(actual) = mismatch {
+            return Err(BuildError::AddressFamilyMismatch {
+                expected: self.address_family,
+                actual,
+            });
+        }
+
+        self.render_addresses(&Addresses::from((source, destination)), buffer)
+    }
+
+    /// Renders the template with the given `Addresses` to the start of the buffer.
+    /// Returns the number of bytes written.
+    /// An error is returned if the addresses do not match the template's address family or the buffer is too small.
+    pub fn render_addresses(
+        &self,
+        addresses: &Addresses,
+        buffer: &mut [u8],
+    ) -> Result<usize, BuildError> {
+        if addresses.address_family() != self.address_family {
+            return Err(BuildError::AddressFamilyMismatch {
+                expected: self.address_family,
+                actual: addresses.address_family(),
+            });
+        }
+
+        let length = self.header.len();
+
+        if buffer.len() < length {
+            return Err(BuildError::Capacity {
+                required: length,
+                available: buffer.len(),
+            });
+        }
+
+        buffer[..length].copy_from_slice(self.header.as_slice());
+        write_addresses(&mut buffer[MINIMUM_LENGTH..], addresses);
+
+        if !self.checksums.is_empty() {
+            crc32c::seal(&mut buffer[..length], &self.checksums);
+        }
+
+        Ok(length)
+    }
+}
+
+impl TryFrom<Builder> for HeaderTemplate {
+    type Error = BuildError;
+
+    /// Builds the header and uses it as a template.
+    /// The built header must be a valid PROXY protocol v2 header without a signature TLV.
+    fn try_from(builder: Builder) -> Result<Self, Self::Error> {
+        let header = builder.build()?;
+        let parsed = Header::try_from(header.as_slice()).map_err(BuildError::Invalid)?;
+        let address_family = parsed.address_family();
+        let tlv_offset = parsed.address_bytes_end();
+        let crc = u8::from(Type::CRC32C);
+        let mut offset = tlv_offset;
+        let mut checksums = Vec::new();
+
+        for tlv in parsed.tlvs().map_while(Result::ok) {
+            #[cfg(feature = "hmac")]
+            if tlv.kind == crate::v2::SIGNATURE_TLV {
+                return Err(BuildError::SignedTemplate);
+            }
+
+            if tlv.kind == crc && tlv.len() == 4 {
+                checksums.push(offset + MINIMUM_TLV_LENGTH);
+            }
+
+            offset += MINIMUM_TLV_LENGTH + tlv.len();
+        }
+
+        Ok(HeaderTemplate {
+            header,
+            address_family,
+            tlv_offset,
+            checksums,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v2::{Command, HeaderMut, IPv4, IPv6, Protocol, Unix, Version};
+
+    fn template<T: Into<Addresses>>(addresses: T) -> HeaderTemplate {
+        HeaderTemplate::try_from(
+            Builder::with_addresses(Version::Two | Command::Proxy, Protocol::Stream, addresses)
+                .write_tlv(Type::NoOp, [0, 0, 0].as_slice())
+                .unwrap()
+                .write_tlv(Type::Authority, b"example.com")
+                .unwrap(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn render_matches_builder() {
+        let template = template(IPv6::new([0; 8], [0; 8], 0, 0));
+        let sources = ["[::1]:80", "[ffff::1]:65535", "[1:2:3:4:5:6:7:8]:1"];
+
+        for source in sources {
+            let source: SocketAddr = source.parse().unwrap();
+            let destination: SocketAddr = "[::2]:443".parse().unwrap();
+            let expected = Builder::with_addresses(
+                Version::Two | Command::Proxy,
+                Protocol::Stream,
+                (source, destination),
+            )
+            .write_tlv(Type::NoOp, [0, 0, 0].as_slice())
+            .unwrap()
+            .write_tlv(Type::Authority, b"example.com")
+            .unwrap()
+            .build()
+            .unwrap();
+
+            let mut buffer = [0; 128];
+            let length = template.render(source, destination, &mut buffer).unwrap();
+
+            assert_eq!(&buffer[..length], expected.as_slice());
+        }
+    }
+
+    #[test]
+    fn render_unix() {
+        let template = template(Unix::new([0; 108], [0; 108]));
+        let addresses: Addresses = Unix::new([0xFF; 108], [0xAA; 108]).into();
+        let mut buffer = vec![0; template.len()];
+
+        template.render_addresses(&addresses, &mut buffer).unwrap();
+
+        let header = Header::try_from(buffer.as_slice()).unwrap();
+
+        assert_eq!(header.addresses, addresses);
+        assert_eq!(header.tlv_bytes(), template.tlv_bytes());
+    }
+
+    #[test]
+    fn render_address_family_mismatch() {
+        let ipv6 = template(IPv6::new([0; 8], [0; 8], 0, 0));
+        let template = template(IPv4::new([0, 0, 0, 0], [0, 0, 0, 0], 0, 0));
+        let mut buffer = [0; 128];
+
+        assert_eq!(
+            template.render(
+                "[::1]:80".parse().unwrap(),
+                "[::2]:443".parse().unwrap(),
+                &mut buffer
+            ),
+            Err(BuildError::AddressFamilyMismatch {
+                expected: AddressFamily::IPv4,
+                actual: AddressFamily::IPv6
+            })
+        );
+        assert_eq!(
+            template.render(
+                "127.0.0.1:80".parse().unwrap(),
+                "[::2]:443".parse().unwrap(),
+                &mut buffer
+            ),
+            Err(BuildError::AddressFamilyMismatch {
+                expected: AddressFamily::IPv4,
+                actual: AddressFamily::IPv6
+            })
+        );
+
+        assert_eq!(
+            ipv6.render(
+                "[::1]:80".parse().unwrap(),
+                "127.0.0.1:443".parse().unwrap(),
+                &mut buffer
+            ),
+            Err(BuildError::AddressFamilyMismatch {
+                expected: AddressFamily::IPv6,
+                actual: AddressFamily::IPv4
+            })
+        );
+    }
+
+    #[test]
+    fn render_buffer_too_small() {
+        let template = template(IPv4::new([0, 0, 0, 0], [0, 0, 0, 0], 0, 0));
+        let mut buffer = [0; 16];
+
+        assert_eq!(
+            template.render(
+                "127.0.0.1:80".parse().unwrap(),
+                "127.0.0.2:443".parse().unwrap(),
+                &mut buffer
+            ),
+            Err(BuildError::Capacity {
+                required: template.len(),
+                available: 16
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_template() {
+        let error = HeaderTemplate::try_from(Builder::new(0x11, 0x11)).unwrap_err();
+
+        assert!(matches!(error, BuildError::Invalid(_)));
+    }
+
+    #[test]
+    fn render_recomputes_crc32c() {
+        let builder = |addresses: Addresses| {
+            Builder::with_addresses(Version::Two | Command::Proxy, Protocol::Stream, addresses)
+                .write_tlv(Type::Authority, b"example.com")
+                .unwrap()
+                .write_tlv(Type::CRC32C, &[0; 4])
+                .unwrap()
+        };
+        let template =
+            HeaderTemplate::try_from(builder(IPv4::new([0; 4], [0; 4], 0, 0).into())).unwrap();
+        let addresses: Addresses = IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443).into();
+        let unsealed = builder(addresses).build().unwrap();
+        let expected = HeaderMut::try_from(&Header::try_from(unsealed.as_slice()).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let mut buffer = [0; 128];
+        let length = template.render_addresses(&addresses, &mut buffer).unwrap();
+
+        assert_eq!(&buffer[..length], expected.as_slice());
+        assert_ne!(&buffer[..length], unsealed.as_slice());
+    }
+
+    #[cfg(feature = "hmac")]
+    #[test]
+    fn signed_template() {
+        let builder = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([0; 4], [0; 4], 0, 0),
+        )
+        .write_tlv(crate::v2::SIGNATURE_TLV, &[0; crate::v2::SIGNATURE_LENGTH])
+        .unwrap();
+
+        assert_eq!(
+            HeaderTemplate::try_from(builder).unwrap_err(),
+            BuildError::SignedTemplate
+        );
+    }
+
+    #[test]
+    fn unspecified_tlv_bytes() {
+        let builder = || {
+            Builder::with_addresses(
+                Version::Two | Command::Local,
+                Protocol::Unspecified,
+                Addresses::Unspecified,
+            )
+            .write_tlv(Type::Authority, b"example.com")
+            .unwrap()
+        };
+        let bytes = builder().build().unwrap();
+        let template = HeaderTemplate::try_from(builder()).unwrap();
+
+        assert_eq!(template.address_family(), AddressFamily::Unspecified);
+        assert_eq!(
+            template.tlv_bytes(),
+            Header::try_from(bytes.as_slice()).unwrap().tlv_bytes()
+        );
+        assert!(template.tlv_bytes().is_empty());
+    }
+}
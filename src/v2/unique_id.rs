@@ -0,0 +1,372 @@
+//! Generation and validation of `UniqueId` TLVs for correlating connections across proxies.
+
+use crate::v2::{Header, ParseError, Type};
+use core::fmt;
+
+/// The maximum length in bytes of a `UniqueId` TLV's value.
+pub const MAX_UNIQUE_ID_LENGTH: usize = 128;
+
+/// The length in bytes of a generated `UniqueId`.
+const GENERATED_LENGTH: usize = 16;
+/// The length in bytes of the timestamp of a time-ordered `UniqueId`.
+const TIMESTAMP_LENGTH: usize = 6;
+
+/// The opaque value of a `UniqueId` TLV, which is at most `MAX_UNIQUE_ID_LENGTH` bytes.
+/// Formats as lowercase hexadecimal.
+///
+/// ## Examples
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use ppp::v2::{Builder, Command, Header, IPv4, Protocol, UniqueId, Version};
+///
+/// let id = UniqueId::new(&[0xAB; 16]).unwrap();
+/// let bytes = Builder::with_addresses(
+///     Version::Two | Command::Proxy,
+///     Protocol::Stream,
+///     IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+/// )
+/// .write_payload(id)
+/// .unwrap()
+/// .build()
+/// .unwrap();
+/// let header = Header::try_from(bytes.as_slice()).unwrap();
+///
+/// assert_eq!(header.unique_id(), Some(Ok(id)));
+/// assert_eq!(id.to_string(), "ab".repeat(16));
+/// assert!(UniqueId::new(&[0; 129]).is_err());
+/// ```
+#[derive(Copy, Clone)]
+pub struct UniqueId {
+    bytes: [u8; MAX_UNIQUE_ID_LENGTH],
+    length: u8,
+}
+
+impl UniqueId {
+    /// Creates a `UniqueId` with a copy of the given bytes.
+    /// An error is returned if there are more than `MAX_UNIQUE_ID_LENGTH` bytes.
+    pub fn new(value: &[u8]) -> Result<Self, ParseError> {
+        if value.len() > MAX_UNIQUE_ID_LENGTH {
+            return Err(ParseError::UniqueIdTooLong(value.len()));
+        }
+
+        let mut bytes = [0; MAX_UNIQUE_ID_LENGTH];
+
+        bytes[..value.len()].copy_from_slice(value);
+
+        Ok(UniqueId {
+            bytes,
+            length: value.len() as u8,
+        })
+    }
+
+    /// The bytes of this `UniqueId`.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.length as usize]
+    }
+
+    /// The length in bytes of this `UniqueId`.
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    /// Tests whether this `UniqueId` has no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+}
+
+impl PartialEq for UniqueId {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl Eq for UniqueId {}
+
+impl fmt::Debug for UniqueId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("UniqueId")
+            .field(&format_args!("{}", self))
+            .finish()
+    }
+}
+
+impl fmt::Display for UniqueId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_bytes()
+            .iter()
+            .try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl TryFrom<&[u8]> for UniqueId {
+    type Error = ParseError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        UniqueId::new(value)
+    }
+}
+
+impl<'a> Header<'a> {
+    /// The value of the first `UniqueId` TLV.
+    /// Returns an error if the TLVs are malformed or the value is longer than `MAX_UNIQUE_ID_LENGTH` bytes.
+    #[cfg_attr(not(feature = "alloc"), allow(clippy::useless_asref))]
+    pub fn unique_id(&self) -> Option<Result<UniqueId, ParseError>> {
+        self.tlv(Type::UniqueId)
+            .map(|tlv| tlv.and_then(|tlv| UniqueId::new(tlv.value.as_ref())))
+    }
+}
+
+/// The format of generated `UniqueId`s.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum UniqueIdFormat {
+    /// 128 random bits.
+    #[default]
+    Random,
+    /// A 48-bit big-endian millisecond timestamp followed by 80 random bits (the ULID layout).
+    /// Identifiers sort by creation time.
+    TimeOrdered,
+}
+
+/// A source of randomness and time for generating `UniqueId`s.
+pub trait IdSource {
+    /// Fills the buffer with random bytes.
+    fn fill_random(&mut self, buffer: &mut [u8]);
+
+    /// The number of milliseconds since the Unix epoch.
+    fn timestamp_millis(&mut self) -> u64;
+}
+
+/// Generates 16-byte `UniqueId`s from an `IdSource`.
+///
+/// ## Examples
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use ppp::v2::{StdIdSource, UniqueIdFormat, UniqueIdGenerator};
+///
+/// let mut generator = UniqueIdGenerator::new(StdIdSource::new()).format(UniqueIdFormat::TimeOrdered);
+/// let first = generator.generate();
+/// let second = generator.generate();
+///
+/// assert_eq!(first.len(), 16);
+/// assert_ne!(first, second);
+/// assert!(first.as_bytes()[..6] <= second.as_bytes()[..6]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct UniqueIdGenerator<S> {
+    source: S,
+    format: UniqueIdFormat,
+}
+
+impl<S: IdSource> UniqueIdGenerator<S> {
+    /// Creates a generator of random `UniqueId`s.
+    pub fn new(source: S) -> Self {
+        UniqueIdGenerator {
+            source,
+            format: UniqueIdFormat::Random,
+        }
+    }
+
+    /// Sets the format of the generated `UniqueId`s.
+    pub fn format(mut self, format: UniqueIdFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Generates a new `UniqueId`.
+    pub fn generate(&mut self) -> UniqueId {
+        let mut bytes = [0; GENERATED_LENGTH];
+
+        match self.format {
+            UniqueIdFormat::Random => self.source.fill_random(&mut bytes),
+            UniqueIdFormat::TimeOrdered => {
+                let timestamp = self.source.timestamp_millis().to_be_bytes();
+
+                bytes[..TIMESTAMP_LENGTH]
+                    .copy_from_slice(&timestamp[timestamp.len() - TIMESTAMP_LENGTH..]);
+                self.source.fill_random(&mut bytes[TIMESTAMP_LENGTH..]);
+            }
+        }
+
+        UniqueId::new(&bytes).expect("generated IDs are shorter than the maximum length")
+    }
+}
+
+/// An `IdSource` backed by the system clock and the standard library's randomly keyed hasher.
+/// The bytes are a keyed SipHash of an incrementing counter: unique within a process, but not drawn from
+/// a cryptographically secure random number generator, so the IDs must not be relied on to be unpredictable.
+/// Implement `IdSource` with a secure random number generator when IDs must be unguessable.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct StdIdSource {
+    state: std::collections::hash_map::RandomState,
+    counter: u64,
+}
+
+#[cfg(feature = "std")]
+impl StdIdSource {
+    /// Creates an `IdSource` with freshly generated random keys.
+    pub fn new() -> Self {
+        StdIdSource {
+            state: std::collections::hash_map::RandomState::new(),
+            counter: 0,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for StdIdSource {
+    fn default() -> Self {
+        StdIdSource::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl IdSource for StdIdSource {
+    fn fill_random(&mut self, buffer: &mut [u8]) {
+        use std::hash::BuildHasher;
+
+        for chunk in buffer.chunks_mut(8) {
+            self.counter = self.counter.wrapping_add(1);
+
+            let random = self.state.hash_one(self.counter).to_be_bytes();
+
+            chunk.copy_from_slice(&random[..chunk.len()]);
+        }
+    }
+
+    fn timestamp_millis(&mut self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "std")]
+mod write {
+    use super::UniqueId;
+    use crate::v2::{Type, TypeLengthValue, WriteToHeader, Writer};
+    use std::io;
+
+    impl WriteToHeader for UniqueId {
+        /// Writes this `UniqueId` as a `UniqueId` TLV.
+        fn write_to(&self, writer: &mut Writer) -> io::Result<usize> {
+            TypeLengthValue::new(Type::UniqueId, self.as_bytes()).write_to(writer)
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod codec {
+    use super::UniqueId;
+    use crate::v2::{ParseError, TlvCodec, Type};
+    use alloc::vec::Vec;
+
+    impl TlvCodec for UniqueId {
+        const KIND: u8 = Type::UniqueId as u8;
+        type Error = ParseError;
+
+        fn decode(value: &[u8]) -> Result<Self, Self::Error> {
+            UniqueId::new(value)
+        }
+
+        fn encode(&self) -> Vec<u8> {
+            self.as_bytes().to_vec()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::v2::{Builder, Command, IPv4, Protocol, Version};
+
+    /// An `IdSource` with a fixed clock and a counting random number generator.
+    struct FixedSource(u8);
+
+    impl IdSource for FixedSource {
+        fn fill_random(&mut self, buffer: &mut [u8]) {
+            for byte in buffer {
+                self.0 += 1;
+                *byte = self.0;
+            }
+        }
+
+        fn timestamp_millis(&mut self) -> u64 {
+            0x0102_0304_0506
+        }
+    }
+
+    fn header(value: &[u8]) -> Vec<u8> {
+        Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .write_tlv(Type::UniqueId, value)
+        .unwrap()
+        .build()
+        .unwrap()
+    }
+
+    #[test]
+    fn generate() {
+        let mut random = UniqueIdGenerator::new(FixedSource(0));
+        let mut ordered =
+            UniqueIdGenerator::new(FixedSource(0)).format(UniqueIdFormat::TimeOrdered);
+
+        assert_eq!(
+            random.generate().as_bytes(),
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]
+        );
+        assert_eq!(
+            ordered.generate().as_bytes(),
+            &[1, 2, 3, 4, 5, 6, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10]
+        );
+    }
+
+    #[test]
+    fn unique_id_length() {
+        let maximum = header(&[7; MAX_UNIQUE_ID_LENGTH]);
+        let long = header(&[7; MAX_UNIQUE_ID_LENGTH + 1]);
+        let maximum = Header::try_from(maximum.as_slice()).unwrap();
+        let long = Header::try_from(long.as_slice()).unwrap();
+
+        assert_eq!(
+            maximum.unique_id().unwrap().unwrap().as_bytes(),
+            [7; MAX_UNIQUE_ID_LENGTH].as_slice()
+        );
+        assert_eq!(
+            long.unique_id(),
+            Some(Err(ParseError::UniqueIdTooLong(MAX_UNIQUE_ID_LENGTH + 1)))
+        );
+        assert_eq!(
+            long.decode_tlv::<UniqueId>(),
+            Some(Err(crate::v2::CodecError::Decode(
+                ParseError::UniqueIdTooLong(MAX_UNIQUE_ID_LENGTH + 1)
+            )))
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let id = UniqueIdGenerator::new(StdIdSource::new()).generate();
+        let bytes = Builder::with_addresses(
+            Version::Two | Command::Proxy,
+            Protocol::Stream,
+            IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+        )
+        .write_payload(id)
+        .unwrap()
+        .build()
+        .unwrap();
+        let header = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(header.decode_tlv::<UniqueId>(), Some(Ok(id)));
+        assert_eq!(
+            format!("{:?}", UniqueId::new(&[0, 255]).unwrap()),
+            "UniqueId(00ff)"
+        );
+    }
+}
@@ -0,0 +1,211 @@
+//! Serialization of the PROXY protocol models with `serde`.
+
+use ppp::v2::{Builder, Command, IPv4, Protocol, Type, Unix, Version};
+use ppp::{v1, v2, ProxyHeader};
+use serde_json::json;
+
+fn unix_path(path: &[u8]) -> [u8; 108] {
+    let mut address = [0; 108];
+
+    address[..path.len()].copy_from_slice(path);
+    address
+}
+
+fn header() -> Vec<u8> {
+    Builder::with_addresses(
+        Version::Two | Command::Proxy,
+        Protocol::Stream,
+        IPv4::new([127, 0, 0, 1], [192, 168, 1, 1], 80, 443),
+    )
+    .write_tlv(Type::Authority, b"example.com")
+    .unwrap()
+    .write_tlv(0xE0, [1, 2].as_slice())
+    .unwrap()
+    .build()
+    .unwrap()
+}
+
+#[test]
+fn json_v1_addresses() {
+    let addresses = v1::Addresses::new_tcp6([0, 0, 0, 0, 0, 0, 0, 1], [0xFFFF; 8], 80, 443);
+    let value = serde_json::to_value(addresses).unwrap();
+
+    assert_eq!(
+        value,
+        json!({"Tcp6": {
+            "source_address": "::1",
+            "source_port": 80,
+            "destination_address": "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+            "destination_port": 443
+        }})
+    );
+    assert_eq!(
+        serde_json::from_value::<v1::Addresses>(value).unwrap(),
+        addresses
+    );
+}
+
+#[test]
+fn json_unix() {
+    let unix = Unix::new(unix_path(b"/tmp/source.sock"), unix_path(&[0, b'a']));
+    let value = serde_json::to_value(v2::Addresses::from(unix)).unwrap();
+
+    assert_eq!(
+        value,
+        json!({"Unix": {"source": "/tmp/source.sock", "destination": "\u{0}a"}})
+    );
+    assert_eq!(
+        serde_json::from_value::<v2::Addresses>(value).unwrap(),
+        unix.into()
+    );
+}
+
+#[test]
+fn json_unix_invalid_utf8() {
+    let unix = Unix::new(unix_path(&[0xFF, 0xFE]), [0; 108]);
+    let value = serde_json::to_value(unix).unwrap();
+
+    assert_eq!(value, json!({"source": [255, 254], "destination": ""}));
+    assert_eq!(serde_json::from_value::<Unix>(value).unwrap(), unix);
+}
+
+#[test]
+fn json_unix_too_long() {
+    let path = "a".repeat(109);
+
+    assert!(serde_json::from_value::<Unix>(json!({"source": path, "destination": ""})).is_err());
+}
+
+#[test]
+fn json_tlv() {
+    let tlv = v2::TypeLengthValue::new(Type::ALPN, b"h2");
+    let value = serde_json::to_value(&tlv).unwrap();
+
+    assert_eq!(value, json!({"kind": "ALPN", "value": [104, 50]}));
+    assert_eq!(
+        serde_json::from_value::<v2::TypeLengthValue>(value).unwrap(),
+        tlv
+    );
+
+    let custom = v2::TypeLengthValue::new(0xE1, &[]);
+    let value = serde_json::to_value(&custom).unwrap();
+
+    assert_eq!(value, json!({"kind": 225, "value": []}));
+    assert_eq!(
+        serde_json::from_value::<v2::TypeLengthValue>(value).unwrap(),
+        custom
+    );
+}
+
+#[test]
+fn json_v2_header() {
+    let bytes = header();
+    let header = v2::Header::try_from(bytes.as_slice()).unwrap();
+    let value = serde_json::to_value(&header).unwrap();
+
+    assert_eq!(
+        value,
+        json!({
+            "version": "Two",
+            "command": "Proxy",
+            "protocol": "Stream",
+            "addresses": {"IPv4": {
+                "source_address": "127.0.0.1",
+                "source_port": 80,
+                "destination_address": "192.168.1.1",
+                "destination_port": 443
+            }},
+            "tlvs": [
+                {"kind": "Authority", "value": b"example.com"},
+                {"kind": 224, "value": [1, 2]}
+            ]
+        })
+    );
+    assert_eq!(serde_json::from_value::<v2::Header>(value).unwrap(), header);
+}
+
+#[test]
+fn json_v2_header_unspecified() {
+    let bytes = Builder::with_addresses(
+        Version::Two | Command::Local,
+        Protocol::Unspecified,
+        v2::Addresses::Unspecified,
+    )
+    .write_payload([1, 2, 3].as_slice())
+    .unwrap()
+    .build()
+    .unwrap();
+    let header = v2::Header::try_from(bytes.as_slice()).unwrap();
+    let value = serde_json::to_value(&header).unwrap();
+
+    assert_eq!(
+        value,
+        json!({
+            "version": "Two",
+            "command": "Local",
+            "protocol": "Unspecified",
+            "addresses": "Unspecified",
+            "payload": [1, 2, 3],
+            "tlvs": []
+        })
+    );
+    assert_eq!(serde_json::from_value::<v2::Header>(value).unwrap(), header);
+}
+
+#[test]
+fn json_v1_header() {
+    let header = v1::Header::try_from("PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\n").unwrap();
+    let value = serde_json::to_value(&header).unwrap();
+
+    assert_eq!(value, json!("PROXY TCP4 127.0.0.1 192.168.1.1 80 443\r\n"));
+    assert_eq!(serde_json::from_value::<v1::Header>(value).unwrap(), header);
+    assert!(serde_json::from_value::<v1::Header>(json!("PROXY TCP4\r\n")).is_err());
+}
+
+#[test]
+fn json_proxy_header() {
+    let bytes = header();
+    let header = ProxyHeader::from(&v2::Header::try_from(bytes.as_slice()).unwrap());
+    let value = serde_json::to_value(&header).unwrap();
+
+    assert_eq!(value["source"], json!("127.0.0.1:80"));
+    assert_eq!(value["transport"], json!("Stream"));
+    assert_eq!(value["tlvs"][0]["kind"], json!("Authority"));
+    assert_eq!(
+        serde_json::from_value::<ProxyHeader>(value).unwrap(),
+        header
+    );
+}
+
+#[test]
+fn bincode_round_trip() {
+    let bytes = header();
+    let v2_header = v2::Header::try_from(bytes.as_slice()).unwrap();
+    let encoded = bincode::serialize(&v2_header).unwrap();
+
+    assert_eq!(
+        bincode::deserialize::<v2::Header>(&encoded).unwrap(),
+        v2_header
+    );
+
+    let proxy_header = ProxyHeader::from(&v2_header);
+    let encoded = bincode::serialize(&proxy_header).unwrap();
+
+    assert_eq!(
+        bincode::deserialize::<ProxyHeader>(&encoded).unwrap(),
+        proxy_header
+    );
+
+    let unix = Unix::new([0xFF; 108], unix_path(b"/tmp/destination.sock"));
+    let encoded = bincode::serialize(&unix).unwrap();
+
+    assert_eq!(bincode::deserialize::<Unix>(&encoded).unwrap(), unix);
+
+    let v1_header = v1::Header::try_from("PROXY UNKNOWN\r\n").unwrap();
+    let encoded = bincode::serialize(&v1_header).unwrap();
+
+    assert_eq!(
+        bincode::deserialize::<v1::Header>(&encoded).unwrap(),
+        v1_header
+    );
+}